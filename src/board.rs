@@ -1,29 +1,52 @@
 //! Board definition
 //!
-//! This defines the board and its operations. For now this represents
-//! a concreat implementation of the board. Maybe in the future we will
-//! want to make the board definition a trait so that we can try out
-//! different implementations of it and check for the performance
-//! implications
+//! This defines the board and its operations. The board is stored as a
+//! bitboard: one bit set for the spaces that are part of the board and
+//! one for the spaces holding a piece. This keeps the board small to copy
+//! and hash and lets move generation work on all pieces at once with
+//! shifts and masks.
+//!
+//! The shape of the board is given by the set of spaces that are part of
+//! it, so the same code works for the English cross and the other
+//! classic boards, each one with its own constructor.
 
 /*
- * Board is a 7x7 matrix
+ * Board is a square matrix of up to 9x9 stored in bit sets.
  * Some of the fields are not used.
  */
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone)]
 pub struct Board {
-    board: [[Space; 7]; 7],
+    /// spaces that hold a piece
+    pieces: Bits,
+    /// spaces that are part of the board
+    holes: Bits,
+    /// number of rows and columns
+    size: usize,
+    rules: Rules,
 }
 
 use crate::error::Error::*;
 use crate::error::{Error, INVALID_MOVE_MESSAGE};
-use crate::move_struct::Move;
+use crate::move_struct::{Move, MultiJump};
 use crate::position::Position;
+use crate::rules::Rules;
 use std::fmt;
 use Space::*;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub(crate) enum Space {
+/// Bit set over the spaces of the board, position (x, y) is the bit
+/// `y * STRIDE + x`.
+pub(crate) type Bits = u128;
+
+/// Largest number of rows and columns of a board.
+pub(crate) const MAX_SIZE: usize = 9;
+
+/// Distance in bits between two rows. The extra column is never part of
+/// the board, so a jump shifted past the end of a row lands on or skips
+/// over it and is never valid.
+pub(crate) const STRIDE: usize = MAX_SIZE + 1;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Space {
     Empty,
     Occupied,
     NotPartOfBoard,
@@ -40,32 +63,54 @@ impl fmt::Debug for Board {
  *
  */
 impl Board {
-    pub fn move_piece(&self, from: Position, to: Position) -> Result<Board, Error> {
+    pub fn move_piece(&self, from: Position, to: Position) -> Result<Board, Error<'static>> {
         let middle = self.valid_move(Move { from, to })?;
         let mut new_board = (*self).clone();
-        new_board.set(from, Empty);
-        new_board.set(to, Occupied);
-        new_board.set(middle, Empty);
+        new_board.pieces ^= bit(from) | bit(middle) | bit(to);
+
+        Ok(new_board)
+    }
 
+    /// Applies all the jumps of the chain, or none of them if one is not
+    /// valid. A chain without jumps is not a move.
+    pub fn apply_multi_jump(&self, chain: &MultiJump) -> Result<Board, Error<'static>> {
+        if chain.path.is_empty() {
+            return Err(EmptyChain);
+        }
+        let mut new_board = self.clone();
+        for (i, m) in chain.jumps().enumerate() {
+            new_board = match new_board.move_piece(m.from, m.to) {
+                Ok(board) => board,
+                Err(err) => return Err(InvalidJumpInChain(i, Box::new(err))),
+            };
+        }
         Ok(new_board)
     }
 
     /// returns the middle position if valid
-    pub(crate) fn valid_move(&self, movement: Move) -> Result<Position, Error> {
+    pub fn valid_move(&self, movement: Move) -> Result<Position, Error<'static>> {
         let (to, from) = (movement.to, movement.from);
         let diff = to - from;
-        if diff.0 != 0 && diff.1 != 0 {
-            return Err(UnalinedMove(INVALID_MOVE_MESSAGE, self.clone(), movement));
-        }
-        if diff.0.abs() + diff.1.abs() != 2 {
-            return Err(DistanceNot2Move(
-                INVALID_MOVE_MESSAGE,
-                self.clone(),
-                movement,
-            ));
-        }
+        let step = match self.rules.step_of(diff) {
+            Some((step, 2)) => step,
+            Some(_) => {
+                return Err(DistanceNot2Move(
+                    INVALID_MOVE_MESSAGE,
+                    self.clone(),
+                    movement,
+                ))
+            }
+            None if diff == (0, 0) => {
+                return Err(DistanceNot2Move(
+                    INVALID_MOVE_MESSAGE,
+                    self.clone(),
+                    movement,
+                ))
+            }
+            None => return Err(UnalinedMove(INVALID_MOVE_MESSAGE, self.clone(), movement)),
+        };
 
-        let middle: Position = from + (diff.0 / 2, diff.1 / 2);
+        let middle: Position = from + step;
         if self.at(from) != Occupied || self.at(to) != Empty || self.at(middle) != Occupied {
             return Err(SpacesInvolvedNotCorrect(
                 INVALID_MOVE_MESSAGE,
@@ -77,15 +122,105 @@ impl Board {
         Ok(middle)
     }
 
-    pub(crate) fn at(&self, pos: Position) -> Space {
-        self.board[pos.y][pos.x]
+    /// Compact identity of the pieces on the board. Bit `y * STRIDE + x`
+    /// is set when that space is occupied, so two boards with the same
+    /// shape have the same code only if they have the same pieces.
+    pub(crate) fn hash_code(&self) -> Bits {
+        self.pieces
+    }
+
+    pub(crate) fn pieces(&self) -> Bits {
+        self.pieces
+    }
+
+    pub fn count_pieces(&self) -> u32 {
+        self.pieces.count_ones()
+    }
+
+    pub(crate) fn holes(&self) -> Bits {
+        self.holes
+    }
+
+    /// Number of rows and columns of the board.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// The same board, played with other jump rules. The serialized board
+    /// does not keep the rules, square boards are always read back as
+    /// orthogonal.
+    pub fn with_rules(&self, rules: Rules) -> Board {
+        Board::from_bits(self.pieces, self.holes, self.size, rules)
+    }
+
+    /// Board with the same shape and the given pieces.
+    pub(crate) fn with_pieces(&self, pieces: Bits) -> Board {
+        Board::from_bits(pieces, self.holes, self.size, self.rules)
+    }
+
+    /// Spaces that are part of the board and hold no piece.
+    pub(crate) fn empty(&self) -> Bits {
+        self.holes & !self.pieces
+    }
+
+    pub fn at(&self, pos: Position) -> Space {
+        if pos.x >= self.size || pos.y >= self.size || self.holes & bit(pos) == 0 {
+            NotPartOfBoard
+        } else if self.pieces & bit(pos) != 0 {
+            Occupied
+        } else {
+            Empty
+        }
     }
 
     pub(crate) fn set(&mut self, pos: Position, val: Space) {
-        self.board[pos.y][pos.x] = val;
+        assert!(
+            pos.x < self.size && pos.y < self.size,
+            "{:?} is outside of the board",
+            pos
+        );
+        let b = bit(pos);
+        match val {
+            Empty => {
+                self.holes |= b;
+                self.pieces &= !b;
+            }
+            Occupied => {
+                self.holes |= b;
+                self.pieces |= b;
+            }
+            NotPartOfBoard => {
+                self.holes &= !b;
+                self.pieces &= !b;
+            }
+        }
     }
 }
 
+/// Bit of a position in a `Bits` set.
+pub(crate) fn bit(pos: Position) -> Bits {
+    1 << (pos.y * STRIDE + pos.x)
+}
+
+/// Positions of the bits set, in row major order.
+pub(crate) fn positions(mut bits: Bits) -> impl Iterator<Item = Position> {
+    std::iter::from_fn(move || {
+        if bits == 0 {
+            return None;
+        }
+        let index = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        Some(Position {
+            x: index % STRIDE,
+            y: index / STRIDE,
+        })
+    })
+}
+
 /*
  * Board constructors
  * they are all static methods
@@ -93,8 +228,8 @@ impl Board {
 impl Board {
     pub fn new() -> Board {
         #[rustfmt::skip]
-        let b = Board {
-            board: [
+        let b = Board::from_array(
+            [
                 [NotPartOfBoard,  NotPartOfBoard, Occupied, Occupied, Occupied, NotPartOfBoard,  NotPartOfBoard],
                 [NotPartOfBoard,  NotPartOfBoard, Occupied, Occupied, Occupied, NotPartOfBoard,  NotPartOfBoard],
                 [Occupied,        Occupied,       Occupied, Occupied, Occupied, Occupied,        Occupied],
@@ -103,13 +238,147 @@ impl Board {
                 [NotPartOfBoard,  NotPartOfBoard, Occupied, Occupied, Occupied, NotPartOfBoard,  NotPartOfBoard],
                 [NotPartOfBoard,  NotPartOfBoard, Occupied, Occupied, Occupied, NotPartOfBoard,  NotPartOfBoard],
             ]
-        };
+        );
+
+        b
+    }
+
+    /// The English board, with 33 holes.
+    pub fn english() -> Board {
+        Board::new()
+    }
+
+    /// The French board, with 37 holes. This is the English board with a
+    /// hole added inside each corner.
+    pub fn french() -> Board {
+        Board::deserialize(
+            "
+  ***  
+ ***** 
+*******
+***-***
+*******
+ ***** 
+  ***  
+",
+        )
+    }
+
+    /// The board of J. C. Wiegleb, with 45 holes. It has arms 3 holes wide
+    /// and 3 holes long.
+    pub fn wiegleb() -> Board {
+        Board::deserialize(
+            "
+   ***   
+   ***   
+   ***   
+*********
+****-****
+*********
+   ***   
+   ***   
+   ***   
+",
+        )
+    }
+
+    /// The diamond board, with 41 holes.
+    pub fn diamond() -> Board {
+        Board::deserialize(
+            "
+    *    
+   ***   
+  *****  
+ ******* 
+****-****
+ ******* 
+  *****  
+   ***   
+    *    
+",
+        )
+    }
+
+    /// The asymmetric board, with 39 holes. It has arms 3 holes wide, two
+    /// of them 3 holes long and the other two 2 holes long.
+    pub fn asymmetric() -> Board {
+        Board::deserialize(
+            "
+   ***  
+   ***  
+   ***  
+********
+****-***
+********
+   ***  
+   ***  
+",
+        )
+    }
+
+    /// The triangular board, with 15 holes and the top one empty.
+    pub fn triangle() -> Board {
+        Board::deserialize(
+            "
+    -    
+   * *   
+  * * *  
+ * * * * 
+* * * * *
+",
+        )
+    }
+
+    /// The classic boards with their names, in their starting position.
+    pub fn standard() -> Vec<(&'static str, Board)> {
+        vec![
+            ("english", Board::english()),
+            ("french", Board::french()),
+            ("wiegleb", Board::wiegleb()),
+            ("diamond", Board::diamond()),
+            ("asymmetric", Board::asymmetric()),
+            ("triangle", Board::triangle()),
+        ]
+    }
+
+    /// One of the `standard` boards by name.
+    pub fn named(name: &str) -> Option<Board> {
+        Board::standard()
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, board)| board)
+    }
+
+    pub(crate) fn from_bits(pieces: Bits, holes: Bits, size: usize, rules: Rules) -> Board {
+        Board {
+            pieces: pieces & holes,
+            holes,
+            size,
+            rules,
+        }
+    }
+
+    /// Board of the given size without any spaces.
+    pub(crate) fn empty_of_size(size: usize, rules: Rules) -> Board {
+        assert!(size <= MAX_SIZE, "boards are at most {0}x{0}", MAX_SIZE);
+        Board::from_bits(0, 0, size, rules)
+    }
 
+    pub(crate) fn from_array<const N: usize>(board: [[Space; N]; N]) -> Board {
+        let mut b = Board::empty_of_size(N, Rules::Orthogonal);
+        for (y, row) in board.iter().enumerate() {
+            for (x, space) in row.iter().enumerate() {
+                b.set(Position { x, y }, *space);
+            }
+        }
         b
     }
+}
 
-    pub(crate) fn from_array(board: [[Space; 7]; 7]) -> Board {
-        Board { board }
+/// The English board.
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
     }
 }
 
@@ -279,4 +548,142 @@ mod tests {
             board.move_piece(from, to)
         );
     }
+
+    #[test]
+    fn multi_jump() {
+        let board = Board::deserialize(
+            "
+  ---  
+  ---  
+-*-*---
+----*--
+----*--
+  ---  
+  ---  
+",
+        );
+        let chain = MultiJump {
+            from: Position { x: 4, y: 4 },
+            path: vec![Position { x: 4, y: 2 }, Position { x: 2, y: 2 }],
+        };
+        let expected = "
+  ---  
+  ---  
+-**----
+-------
+-------
+  ---  
+  ---  
+";
+        let got = board
+            .apply_multi_jump(&chain)
+            .expect("valid chain")
+            .serialize();
+        assert_eq!(expected, got, "\nExpected: {}\nGot: {}", expected, got);
+    }
+
+    #[test]
+    fn multi_jump_reports_failed_hop() {
+        let board = Board::new();
+        let chain = MultiJump {
+            from: Position { x: 3, y: 1 },
+            path: vec![Position { x: 3, y: 3 }, Position { x: 3, y: 5 }],
+        };
+        let after_first = board
+            .move_piece(Position { x: 3, y: 1 }, Position { x: 3, y: 3 })
+            .expect("valid move");
+
+        assert_eq!(
+            Err(InvalidJumpInChain(
+                1,
+                Box::new(SpacesInvolvedNotCorrect(
+                    INVALID_MOVE_MESSAGE,
+                    after_first,
+                    Move {
+                        from: Position { x: 3, y: 3 },
+                        to: Position { x: 3, y: 5 },
+                    }
+                ))
+            )),
+            board.apply_multi_jump(&chain)
+        );
+    }
+
+    #[test]
+    fn empty_chain_is_not_a_move() {
+        let chain = MultiJump {
+            from: Position { x: 3, y: 1 },
+            path: vec![],
+        };
+        assert_eq!(Err(EmptyChain), Board::new().apply_multi_jump(&chain));
+    }
+
+    #[test]
+    fn other_geometries() {
+        let boards = [
+            (Board::english(), 33, 8),
+            (Board::french(), 37, 8),
+            (Board::wiegleb(), 45, 8),
+            (Board::diamond(), 41, 8),
+            (Board::asymmetric(), 39, 2),
+        ];
+        for (board, holes, symmetries) in boards {
+            assert_eq!(holes, board.holes().count_ones());
+            assert_eq!(holes - 1, board.count_pieces());
+            assert_eq!(4, board.find_moves().len());
+            assert_eq!(symmetries, board.symmetries().len());
+            assert_eq!(board, Board::deserialize(&board.serialize()));
+        }
+    }
+
+    #[test]
+    fn moves_outside_of_7x7() {
+        let board = Board::wiegleb();
+        let (from, to) = (Position { x: 6, y: 4 }, Position { x: 4, y: 4 });
+        let board = board.move_piece(from, to).expect("valid move");
+        let (from, to) = (Position { x: 8, y: 4 }, Position { x: 6, y: 4 });
+        let expected = "
+   ***   
+   ***   
+   ***   
+*********
+*****-*--
+*********
+   ***   
+   ***   
+   ***   
+";
+        let got = board.move_piece(from, to).expect("valid move").serialize();
+        assert_eq!(expected, got, "\nExpected: {}\nGot: {}", expected, got);
+    }
+
+    #[test]
+    fn diagonal_move() {
+        let (from, to) = (Position { x: 1, y: 2 }, Position { x: 3, y: 4 });
+        let board = Board::french()
+            .move_piece(Position { x: 3, y: 5 }, Position { x: 3, y: 3 })
+            .expect("valid move");
+
+        assert_eq!(
+            Err(UnalinedMove(
+                INVALID_MOVE_MESSAGE,
+                board.clone(),
+                Move { from, to }
+            )),
+            board.move_piece(from, to)
+        );
+
+        let board = board.with_rules(Rules::Diagonal);
+        let expected = "
+  ***  
+ ***** 
+*-*****
+**-****
+*******
+ **-** 
+  ***  
+";
+        let got = board.move_piece(from, to).expect("valid move").serialize();
+        assert_eq!(expected, got, "\nExpected: {}\nGot: {}", expected, got);
+    }
 }
@@ -2,60 +2,225 @@
 //!
 //! Allos for Board to be serialized to string and deserialized from
 //! string.
+//!
+//! Triangular boards are written as a triangle, with the spaces of each
+//! row separated by a blank and each row shifted half a space from the
+//! one above. They are told apart from square boards by being almost
+//! twice as wide as they are tall.
+//!
+//! Reading a board never panics on bad input, the errors tell the line and
+//! column of the problem. Boards read with `FromStr` must have the shape
+//! of one of the standard boards.
 
-use crate::board::Board;
 use crate::board::Space::*;
+use crate::board::{positions, Board, Space, MAX_SIZE};
+use crate::error::{ParseError, ParseErrorKind};
 use crate::position::Position;
+use crate::rules::Rules;
+use std::str::FromStr;
 
 /// Serialize and deserialize Board
 impl Board {
-    pub(crate) fn serialize(&self) -> String {
+    pub fn serialize(&self) -> String {
+        if self.rules() == Rules::Triangular {
+            return self.serialize_triangle();
+        }
         let mut b_str = String::new();
-        b_str.push_str("\n");
-        for y in 0..7 {
-            for x in 0..7 {
-                match self.at(Position { x, y }) {
-                    NotPartOfBoard => b_str.push_str(" "),
-                    Empty => b_str.push_str("-"),
-                    Occupied => b_str.push_str("*"),
+        b_str.push('\n');
+        for y in 0..self.size() {
+            for x in 0..self.size() {
+                b_str.push(space_char(self.at(Position { x, y })));
+            }
+            b_str.push('\n');
+        }
+        b_str
+    }
+
+    fn serialize_triangle(&self) -> String {
+        let size = self.size();
+        let mut b_str = String::new();
+        b_str.push('\n');
+        for y in 0..size {
+            let margin = " ".repeat(size - 1 - y);
+            b_str.push_str(&margin);
+            for x in 0..=y {
+                if x > 0 {
+                    b_str.push(' ');
                 }
+                b_str.push(space_char(self.at(Position { x, y })));
             }
-            b_str.push_str("\n");
+            b_str.push_str(&margin);
+            b_str.push('\n');
         }
         b_str
     }
-    pub(crate) fn deserialize(board_str: &str) -> Board {
-        let mut board = Board::new();
-        let (mut x, mut y) = (0, 0);
-        for n in 1..board_str.len() {
-            let char = board_str.chars().nth(n);
-            match char {
-                None => return board,
-                Some(char) => match char {
-                    ' ' => {
-                        board.set(Position { x, y }, NotPartOfBoard);
-                        x += 1;
-                    }
-                    '-' => {
-                        board.set(Position { x, y }, Empty);
-                        x += 1;
-                    }
-                    '*' => {
-                        board.set(Position { x, y }, Occupied);
-                        x += 1;
-                    }
-                    '\n' => {
-                        x = 0;
-                        y += 1;
-                    }
-                    _ => panic!("Invalid char '{}'", char),
+
+    /// Reads a board of any shape, the spaces that are not part of the
+    /// board being blank. The leading newline written by `serialize` and
+    /// blanks at the end of the rows are optional.
+    pub fn parse_any_shape(board_str: &str) -> Result<Board, ParseError> {
+        Ok(parse_spaces(board_str)?.0)
+    }
+
+    /// Reads a board that must have the same shape and rules as `shape`.
+    pub fn parse_with_shape(board_str: &str, shape: &Board) -> Result<Board, ParseError> {
+        let (board, layout) = parse_spaces(board_str)?;
+        let board = board.with_rules(shape.rules());
+        layout.check_shape(&board, shape)?;
+        Ok(board)
+    }
+
+    /// Reads a board that is known to be valid, panics otherwise.
+    pub fn deserialize(board_str: &str) -> Board {
+        match Board::parse_any_shape(board_str) {
+            Ok(board) => board,
+            Err(err) => panic!("Invalid board: {}", err),
+        }
+    }
+}
+
+/// Reads a board with the shape of one of the standard boards.
+impl FromStr for Board {
+    type Err = ParseError;
+
+    fn from_str(board_str: &str) -> Result<Board, ParseError> {
+        let (board, layout) = parse_spaces(board_str)?;
+        let shapes: Vec<Board> = Board::standard()
+            .into_iter()
+            .map(|(_, shape)| shape)
+            .filter(|shape| {
+                shape.size() == board.size()
+                    && (shape.rules() == Rules::Triangular) == layout.triangular
+            })
+            .collect();
+        let closest = shapes
+            .iter()
+            .min_by_key(|shape| (shape.holes() ^ board.holes()).count_ones())
+            .ok_or(ParseError {
+                line: layout.first_line,
+                column: 1,
+                kind: ParseErrorKind::UnknownSize(board.size()),
+            })?;
+        let board = board.with_rules(closest.rules());
+        layout.check_shape(&board, closest)?;
+        Ok(board)
+    }
+}
+
+/// Where the spaces of the board are in the string.
+struct Layout {
+    /// line of the first row
+    first_line: usize,
+    size: usize,
+    triangular: bool,
+}
+
+impl Layout {
+    /// Line and column of a position.
+    fn locate(&self, pos: Position) -> (usize, usize) {
+        let column = if self.triangular {
+            self.size - pos.y + 2 * pos.x
+        } else {
+            pos.x + 1
+        };
+        (self.first_line + pos.y, column)
+    }
+
+    fn check_shape(&self, board: &Board, shape: &Board) -> Result<(), ParseError> {
+        if board.size() != shape.size() || board.rules() != shape.rules() {
+            return Err(ParseError {
+                line: self.first_line,
+                column: 1,
+                kind: ParseErrorKind::WrongSize {
+                    expected: shape.size(),
+                    found: board.size(),
                 },
+            });
+        }
+        match positions(board.holes() ^ shape.holes()).next() {
+            None => Ok(()),
+            Some(pos) => {
+                let (line, column) = self.locate(pos);
+                let kind = if shape.at(pos) == NotPartOfBoard {
+                    ParseErrorKind::HoleOutsideBoard
+                } else {
+                    ParseErrorKind::MissingHole
+                };
+                Err(ParseError { line, column, kind })
             }
-            if char.is_none() {
-                return board;
+        }
+    }
+}
+
+/// Reads the spaces of a board of any shape. Boards that are at least
+/// twice as wide as they are tall, minus one, are read as triangular.
+fn parse_spaces(board_str: &str) -> Result<(Board, Layout), ParseError> {
+    let mut rows: Vec<(usize, &str)> = board_str.lines().zip(1..).map(|(r, l)| (l, r)).collect();
+    if board_str.starts_with('\n') || board_str.starts_with("\r\n") {
+        rows.remove(0);
+    }
+    while rows.last().is_some_and(|(_, row)| row.is_empty()) {
+        rows.pop();
+    }
+
+    let error = |line, column, kind| Err(ParseError { line, column, kind });
+    let size = rows.len();
+    if size == 0 {
+        return error(1, 1, ParseErrorKind::EmptyBoard);
+    }
+    if size > MAX_SIZE {
+        return error(rows[MAX_SIZE].0, 1, ParseErrorKind::TooManyRows);
+    }
+    let width = rows.iter().map(|(_, row)| row.chars().count()).max();
+    let triangular = size > 1 && width.is_some_and(|width| width >= 2 * size - 1);
+    let layout = Layout {
+        first_line: rows[0].0,
+        size,
+        triangular,
+    };
+    let max_width = if triangular { 2 * size - 1 } else { size };
+
+    let rules = if triangular {
+        Rules::Triangular
+    } else {
+        Rules::Orthogonal
+    };
+    let mut board = Board::empty_of_size(size, rules);
+    for (y, (line, row)) in rows.iter().enumerate() {
+        for (c, char) in row.chars().enumerate() {
+            let space = match char {
+                ' ' => NotPartOfBoard,
+                '-' => Empty,
+                '*' => Occupied,
+                _ => return error(*line, c + 1, ParseErrorKind::InvalidChar(char)),
+            };
+            if c >= max_width {
+                return error(*line, c + 1, ParseErrorKind::RowTooLong);
             }
+            let x = if triangular {
+                // cells are every other char after the margin
+                let offset = size - 1 - y;
+                if c < offset || (c - offset) % 2 == 1 {
+                    if space != NotPartOfBoard {
+                        return error(*line, c + 1, ParseErrorKind::HoleOutsideBoard);
+                    }
+                    continue;
+                }
+                (c - offset) / 2
+            } else {
+                c
+            };
+            board.set(Position { x, y }, space);
         }
-        board
+    }
+    Ok((board, layout))
+}
+
+fn space_char(space: Space) -> char {
+    match space {
+        NotPartOfBoard => ' ',
+        Empty => '-',
+        Occupied => '*',
     }
 }
 
@@ -105,4 +270,151 @@ mod tests {
         assert_eq!(expected, got, "\nExpected: {}\nGot: {}", expected, got);
         assert_eq!(non_full_board, Board::deserialize(expected));
     }
+
+    #[test]
+    fn triangle_serialize() {
+        let board = Board::triangle()
+            .move_piece(Position { x: 0, y: 2 }, Position { x: 0, y: 0 })
+            .expect("valid move");
+        let expected = "
+    *    
+   - *   
+  - * *  
+ * * * * 
+* * * * *
+";
+        let got = board.serialize();
+        assert_eq!(expected, got, "\nExpected: {}\nGot: {}", expected, got);
+        assert_eq!(board, Board::deserialize(expected));
+    }
+
+    #[test]
+    fn standard_boards_round_trip() {
+        for (name, board) in Board::standard() {
+            let parsed: Board = board.serialize().parse().expect(name);
+            assert_eq!(board, parsed, "{}", name);
+        }
+    }
+
+    #[test]
+    fn leading_newline_and_trailing_blanks_are_optional() {
+        let board: Board = "  ***\n  ***\n*******\n***-***\n*******\n  ***\n  ***"
+            .parse()
+            .expect("valid board");
+        assert_eq!(Board::english(), board);
+
+        let board: Board = "    *\n   - *\n  - * *\n * * * *\n* * * * *\n"
+            .parse()
+            .expect("valid board");
+        assert_eq!(Board::triangle().rules(), board.rules());
+        assert_eq!(Board::triangle().holes(), board.holes());
+    }
+
+    #[test]
+    fn invalid_char_position() {
+        let err = "
+  ***
+  ***
+*******
+***o***
+*******
+  ***
+  ***
+"
+        .parse::<Board>()
+        .unwrap_err();
+        assert_eq!(
+            ParseError {
+                line: 5,
+                column: 4,
+                kind: ParseErrorKind::InvalidChar('o')
+            },
+            err
+        );
+        assert_eq!(
+            "line 5, column 4: invalid char 'o', expected ' ', '-' or '*'",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn wrong_size() {
+        let err = Board::parse_with_shape("***\n*-*\n***", &Board::english()).unwrap_err();
+        assert_eq!(
+            ParseErrorKind::WrongSize {
+                expected: 7,
+                found: 3
+            },
+            err.kind
+        );
+
+        let err = "*\n*\n".parse::<Board>().unwrap_err();
+        assert_eq!(ParseErrorKind::UnknownSize(2), err.kind);
+
+        let err = "  ***\n  ****\n*******\n***-***\n*******\n  ***\n  ***"
+            .parse::<Board>()
+            .unwrap_err();
+        assert_eq!((2, 6), (err.line, err.column));
+
+        let err = "".parse::<Board>().unwrap_err();
+        assert_eq!(ParseErrorKind::EmptyBoard, err.kind);
+
+        let err = "*\n".repeat(MAX_SIZE + 1).parse::<Board>().unwrap_err();
+        assert_eq!(ParseErrorKind::TooManyRows, err.kind);
+    }
+
+    #[test]
+    fn holes_must_match_the_geometry() {
+        let err = "
+ ****
+  ***
+*******
+***-***
+*******
+  ***
+  ***
+"
+        .parse::<Board>()
+        .unwrap_err();
+        assert_eq!(
+            ParseError {
+                line: 2,
+                column: 2,
+                kind: ParseErrorKind::HoleOutsideBoard
+            },
+            err
+        );
+
+        let err = "
+  ***
+  ***
+*******
+*** ***
+*******
+  ***
+  ***
+"
+        .parse::<Board>()
+        .unwrap_err();
+        assert_eq!(
+            ParseError {
+                line: 5,
+                column: 4,
+                kind: ParseErrorKind::MissingHole
+            },
+            err
+        );
+
+        let err = "    *\n   -**\n  - * *\n * * * *\n* * * * *"
+            .parse::<Board>()
+            .unwrap_err();
+        assert_eq!(
+            ParseError {
+                line: 2,
+                column: 5,
+                kind: ParseErrorKind::HoleOutsideBoard
+            },
+            err
+        );
+    }
 }
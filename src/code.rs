@@ -0,0 +1,187 @@
+//! Compact board codes
+//!
+//! Boards of the same shape only differ in their pieces, so once the shape
+//! is known a board is given by one bit per hole. The code of a board has
+//! bit `i` set when the `i`th hole, in row major order, holds a piece,
+//! which fits in a `u64` for the boards with up to 64 holes, all the
+//! classic ones.
+//!
+//! The boards with `k` pieces can also be ranked: numbered from `0` to
+//! `C(n, k) - 1` for a board of `n` holes, with the combinatorial number
+//! system. The `i`th piece, counting from `0`, at hole `h` adds `C(h, i + 1)`
+//! to the rank. Tables indexed by rank have no gaps, unlike tables indexed
+//! by code.
+
+use crate::board::{bit, positions, Bits, Board};
+
+/// Largest number of holes of a board with a code.
+pub const MAX_CODE_HOLES: u32 = u64::BITS;
+
+impl Board {
+    /// Number of holes of the board.
+    pub fn count_holes(&self) -> u32 {
+        self.holes().count_ones()
+    }
+
+    /// The pieces as a bit per hole, or `None` if the board has more than
+    /// `MAX_CODE_HOLES` holes.
+    pub fn to_code(&self) -> Option<u64> {
+        if self.count_holes() > MAX_CODE_HOLES {
+            return None;
+        }
+        let pieces = self.pieces();
+        let code = positions(self.holes())
+            .enumerate()
+            .filter(|(_, pos)| pieces & bit(*pos) != 0)
+            .fold(0, |code, (i, _)| code | 1 << i);
+        Some(code)
+    }
+
+    /// Board with the same shape and the pieces of a code, or `None` if
+    /// the code has pieces past the last hole or the board has more than
+    /// `MAX_CODE_HOLES` holes.
+    pub fn with_code(&self, code: u64) -> Option<Board> {
+        let holes = self.count_holes();
+        if holes > MAX_CODE_HOLES || (holes < MAX_CODE_HOLES && code >> holes != 0) {
+            return None;
+        }
+        let pieces: Bits = positions(self.holes())
+            .enumerate()
+            .filter(|(i, _)| code & 1 << i != 0)
+            .fold(0, |pieces, (_, pos)| pieces | bit(pos));
+        Some(self.with_pieces(pieces))
+    }
+
+    /// Number of boards of this shape with `pieces` pieces, the ranks of
+    /// those boards go from 0 to one less than this.
+    pub fn count_boards(&self, pieces: u32) -> Option<u64> {
+        binomial(self.count_holes(), pieces)
+    }
+
+    /// Index of the board among the boards of the same shape and number of
+    /// pieces, or `None` if the board has more than `MAX_CODE_HOLES` holes.
+    pub fn rank(&self) -> Option<u64> {
+        let code = self.to_code()?;
+        let mut rank = 0;
+        let mut rest = code;
+        let mut i = 0;
+        while rest != 0 {
+            let hole = rest.trailing_zeros();
+            rank += binomial(hole, i + 1)?;
+            rest &= rest - 1;
+            i += 1;
+        }
+        Some(rank)
+    }
+
+    /// Board of the same shape with `pieces` pieces and the given rank, or
+    /// `None` if there is no such board.
+    pub fn with_rank(&self, pieces: u32, rank: u64) -> Option<Board> {
+        if self.count_holes() > MAX_CODE_HOLES || rank >= self.count_boards(pieces)? {
+            return None;
+        }
+        let mut code = 0;
+        let mut rest = rank;
+        let mut hole = self.count_holes();
+        for i in (1..=pieces).rev() {
+            // largest hole with C(hole, i) <= rest
+            hole -= 1;
+            while binomial(hole, i)? > rest {
+                hole -= 1;
+            }
+            rest -= binomial(hole, i)?;
+            code |= 1 << hole;
+        }
+        self.with_code(code)
+    }
+}
+
+/// `n` choose `k`, or `None` if it does not fit in a `u64`.
+pub(crate) fn binomial(n: u32, k: u32) -> Option<u64> {
+    if k > n {
+        return Some(0);
+    }
+    let k = k.min(n - k) as u64;
+    let mut result: u64 = 1;
+    for i in 0..k {
+        // exact at each step: the product of i + 1 consecutive numbers is
+        // divisible by (i + 1)!
+        let next = result as u128 * (n as u64 - i) as u128 / (i + 1) as u128;
+        result = u64::try_from(next).ok()?;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+    use std::collections::HashSet;
+
+    #[test]
+    fn codes_round_trip() {
+        for (name, board) in Board::standard() {
+            let code = board.to_code().expect(name);
+            assert_eq!(board.count_pieces(), code.count_ones(), "{}", name);
+            assert_eq!(Some(board.clone()), board.with_code(code), "{}", name);
+            for m in board.find_moves() {
+                let next = board.move_piece(m.from, m.to).expect("valid move");
+                let next_code = next.to_code().expect(name);
+                assert_ne!(code, next_code);
+                assert_eq!(Some(next), board.with_code(next_code));
+            }
+        }
+        let english = Board::new();
+        assert_eq!(Some((1 << 33) - 1 - (1 << 16)), english.to_code());
+        assert_eq!(None, english.with_code(1 << 33));
+
+        let holes = (0..9)
+            .flat_map(|y| (0..9).map(move |x| bit(Position { x, y })))
+            .fold(0, |holes, b| holes | b);
+        let square = Board::from_bits(0, holes, 9, english.rules());
+        assert_eq!(81, square.count_holes());
+        assert_eq!(None, square.to_code());
+        assert_eq!(None, square.with_code(0));
+        assert_eq!(None, square.with_code(1));
+        assert_eq!(None, square.rank());
+
+        let sixty_four = Board::from_bits(0, holes & ((1 << 71) - 1), 9, english.rules());
+        assert_eq!(64, sixty_four.count_holes());
+        let last = sixty_four.with_code(1 << 63).expect("fits");
+        assert_eq!(Some(1 << 63), last.to_code());
+    }
+
+    #[test]
+    fn ranks_are_dense() {
+        let triangle = Board::triangle();
+        for pieces in 0..=15 {
+            let count = triangle.count_boards(pieces).expect("fits");
+            let boards: HashSet<Board> = (0..count)
+                .map(|rank| {
+                    let board = triangle.with_rank(pieces, rank).expect("valid rank");
+                    assert_eq!(pieces, board.count_pieces());
+                    assert_eq!(Some(rank), board.rank());
+                    board
+                })
+                .collect();
+            assert_eq!(count as usize, boards.len());
+            assert_eq!(None, triangle.with_rank(pieces, count));
+        }
+        assert_eq!(None, triangle.with_rank(16, 0));
+    }
+
+    #[test]
+    fn english_ranks() {
+        let english = Board::new();
+        assert_eq!(Some(1_166_803_110), english.count_boards(16));
+        assert_eq!(Some(1), english.count_boards(33));
+        let rank = english.rank().expect("fits");
+        assert!(rank < english.count_boards(32).expect("fits"));
+        assert_eq!(Some(english.clone()), english.with_rank(32, rank));
+        let last = english.count_boards(16).expect("fits") - 1;
+        let board = english.with_rank(16, last).expect("valid rank");
+        assert_eq!(Some(last), board.rank());
+        assert_eq!(None, binomial(100, 50));
+        assert_eq!(Some(1), binomial(64, 64));
+    }
+}
@@ -0,0 +1,249 @@
+//! Endgame database
+//!
+//! The canonical forms of all the boards reachable from a start that can
+//! still be reduced to a single piece, built once from an `Enumeration`
+//! and saved to disk. Once loaded, telling if a board can still be won is
+//! a lookup in a hash set.
+//!
+//! Only boards reachable from the start of the database are known, any
+//! other board is reported as not winnable. The solver checks that its
+//! board is in the database before using it, and every board reached from
+//! there is reachable from the start too.
+//!
+//! The file starts with a text header with the rules and the start board,
+//! followed by the sorted codes of the canonical boards as 8 byte little
+//! endian integers, so only boards with up to 64 holes can be saved.
+
+use crate::board::{Bits, Board, MAX_SIZE};
+use crate::enumerate::Enumeration;
+use crate::rules::Rules;
+use crate::symmetry::Canonicalizer;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &str = "peg-solitaire database";
+
+/// Longest line of the header, the rows of the largest boards are far
+/// shorter.
+const MAX_LINE: u64 = 64;
+
+pub struct Database {
+    start: Board,
+    canonicalizer: Canonicalizer,
+    winnable: HashSet<Bits>,
+}
+
+impl Database {
+    /// Enumerates all the boards reachable from `start`, which takes a few
+    /// minutes for the English board.
+    pub fn build(start: &Board) -> Database {
+        Database::from_enumeration(&Enumeration::new(start))
+    }
+
+    pub fn from_enumeration(enumeration: &Enumeration) -> Database {
+        Database::from_codes(enumeration.start().clone(), enumeration.winnable_codes())
+    }
+
+    fn from_codes(start: Board, codes: impl Iterator<Item = Bits>) -> Database {
+        Database {
+            canonicalizer: Canonicalizer::fixing(&start),
+            start,
+            winnable: codes.collect(),
+        }
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    /// Number of winnable boards, counting the rotations and mirrors of a
+    /// board once.
+    pub fn len(&self) -> usize {
+        self.winnable.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.winnable.is_empty()
+    }
+
+    /// True if the board is reachable from the start and can still be
+    /// reduced to a single piece.
+    pub fn winnable(&self, board: &Board) -> bool {
+        board.holes() == self.start.holes()
+            && board.rules() == self.start.rules()
+            && self
+                .winnable
+                .contains(&self.canonicalizer.canonical(board.pieces()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut output = BufWriter::new(File::create(path)?);
+        self.write(&mut output)?;
+        output.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Database> {
+        Database::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let mut codes = self
+            .winnable
+            .iter()
+            .map(|pieces| self.start.with_pieces(*pieces).to_code())
+            .collect::<Option<Vec<u64>>>()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "boards with more than 64 holes can not be saved",
+                )
+            })?;
+        codes.sort_unstable();
+        let rows = self.start.serialize();
+        let rows = rows.trim_start_matches('\n');
+        writeln!(output, "{}", MAGIC)?;
+        writeln!(output, "rules {}", self.start.rules().name())?;
+        writeln!(output, "rows {}", rows.lines().count())?;
+        write!(output, "{}", rows)?;
+        writeln!(output, "codes {}", codes.len())?;
+        for code in codes {
+            output.write_all(&code.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: BufRead>(mut input: R) -> io::Result<Database> {
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let line = |input: &mut R| -> io::Result<String> {
+            let mut line = String::new();
+            input.by_ref().take(MAX_LINE).read_line(&mut line)?;
+            match line.strip_suffix('\n') {
+                Some(line) => Ok(line.to_string()),
+                None => Err(invalid("header line too long or file too short")),
+            }
+        };
+        if line(&mut input)? != MAGIC {
+            return Err(invalid("not a database"));
+        }
+        let rules = line(&mut input)?
+            .strip_prefix("rules ")
+            .and_then(Rules::from_name)
+            .ok_or_else(|| invalid("invalid rules"))?;
+        let rows: usize = line(&mut input)?
+            .strip_prefix("rows ")
+            .and_then(|n| n.parse().ok())
+            .filter(|rows| *rows <= MAX_SIZE)
+            .ok_or_else(|| invalid("invalid number of rows"))?;
+        let board_text = (0..rows)
+            .map(|_| line(&mut input))
+            .collect::<io::Result<Vec<_>>>()?
+            .join("\n");
+        let start = Board::parse_any_shape(&board_text)
+            .map_err(|err| invalid(&format!("invalid start board, {}", err)))?
+            .with_rules(rules);
+        let count: u64 = line(&mut input)?
+            .strip_prefix("codes ")
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| invalid("invalid number of codes"))?;
+
+        // the count is not trusted to size the set, a file with fewer codes
+        // ends early
+        let mut codes = vec![];
+        let mut bytes = [0; 8];
+        for _ in 0..count {
+            input
+                .read_exact(&mut bytes)
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::UnexpectedEof => invalid("fewer codes than announced"),
+                    _ => err,
+                })?;
+            let board = start
+                .with_code(u64::from_le_bytes(bytes))
+                .ok_or_else(|| invalid("code with pieces outside of the board"))?;
+            codes.push(board.pieces());
+        }
+        Ok(Database::from_codes(start, codes.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::bit;
+    use crate::position::Position;
+    use crate::solver::Solver;
+    use std::sync::Arc;
+
+    /// Triangle with the bottom left corner empty.
+    fn triangle_start() -> Board {
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        full.with_pieces(full.pieces() & !bit(Position { x: 0, y: 4 }))
+    }
+
+    #[test]
+    fn write_and_read() {
+        let database = Database::build(&triangle_start());
+        let mut bytes = vec![];
+        database.write(&mut bytes).expect("writes to a vec");
+        let read = Database::read(bytes.as_slice()).expect("valid database");
+
+        assert_eq!(database.start(), read.start());
+        assert_eq!(database.winnable, read.winnable);
+        assert!(!read.is_empty());
+        assert!(Database::read("not a database\n".as_bytes()).is_err());
+        let invalid_data = |bytes: &[u8]| {
+            Database::read(bytes).err().map(|err| err.kind()) == Some(io::ErrorKind::InvalidData)
+        };
+        assert!(invalid_data(&bytes[..bytes.len() - 1]));
+        let header_end = bytes.len() - 8 * database.len();
+        for end in 0..header_end {
+            assert!(invalid_data(&bytes[..end]), "{}", end);
+        }
+        let header = String::from_utf8(bytes[..header_end].to_vec()).expect("text header");
+        let with_header = |from: &str, to: &str| {
+            assert!(header.contains(from));
+            let mut changed = header.replace(from, to).into_bytes();
+            changed.extend_from_slice(&bytes[header_end..]);
+            changed
+        };
+        let codes = format!("codes {}\n", database.len());
+        assert!(Database::read(with_header(&codes, &codes).as_slice()).is_ok());
+        for count in ["18446744073709551615", "100000000000"] {
+            let huge = with_header(&codes, &format!("codes {}\n", count));
+            assert!(invalid_data(&huge));
+        }
+        assert!(invalid_data(&with_header("rows 5\n", "rows 10\n")));
+        assert!(invalid_data(&with_header(
+            "rows 5\n",
+            "rows 18446744073709551615\n"
+        )));
+    }
+
+    #[test]
+    fn same_answers_as_the_solver() {
+        let start = triangle_start();
+        let database = Arc::new(Database::build(&start));
+        let mut seen = HashSet::from([start.clone()]);
+        let mut boards = vec![start];
+        while let Some(board) = boards.pop() {
+            let solution = Solver::from_board(board.clone()).solve();
+            assert_eq!(solution.is_some(), database.winnable(&board), "{}", board);
+
+            let mut solver = Solver::from_board(board.clone()).with_database(database.clone());
+            match solver.solve() {
+                Some(moves) => assert!(solver.verify(&moves).expect("valid").goal_reached),
+                None => assert!(solution.is_none()),
+            }
+            for m in board.find_moves() {
+                let next = board.move_piece(m.from, m.to).expect("valid move");
+                if seen.insert(next.clone()) {
+                    boards.push(next);
+                }
+            }
+        }
+        assert!(!database.winnable(&Board::new()));
+    }
+}
@@ -0,0 +1,244 @@
+//! State space enumeration
+//!
+//! Every move removes a piece, so the boards reachable from a start can be
+//! found one level at a time, each level holding the boards with one piece
+//! less than the one before. Boards are kept in canonical form under the
+//! symmetries that leave the start unchanged, which keeps about an eighth
+//! of them when starting from the center of the square boards. The number
+//! of boards each one stands for is found back from its symmetries.
+//!
+//! Once all the levels are known, a backward pass from the boards with a
+//! single piece marks the boards that can still be won: the ones with a
+//! move to a winnable board of the level below.
+
+use crate::board::{Bits, Board};
+use crate::symmetry::Canonicalizer;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+/// Number of boards of a level.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LevelCount {
+    pub pieces: u32,
+    pub reachable: u64,
+    pub winnable: u64,
+    /// reachable boards, counting the rotations and mirrors of a board once
+    pub reachable_canonical: u64,
+    /// winnable boards, counting the rotations and mirrors of a board once
+    pub winnable_canonical: u64,
+}
+
+pub struct Enumeration {
+    shape: Board,
+    canonicalizer: Canonicalizer,
+    /// canonical pieces of the reachable boards with as many pieces as the
+    /// index, sorted
+    levels: Vec<Vec<Bits>>,
+    /// for each board of `levels`, if it can reach a single piece
+    winnable: Vec<Vec<bool>>,
+}
+
+impl Enumeration {
+    /// All the boards reachable from `start`, and which of them can still
+    /// reach a single piece.
+    pub fn new(start: &Board) -> Enumeration {
+        let canonicalizer = Canonicalizer::fixing(start);
+        let top = start.count_pieces() as usize;
+        let mut levels = vec![vec![]; top + 1];
+        levels[top].push(canonicalizer.canonical(start.pieces()));
+        for pieces in (1..top).rev() {
+            let mut next = HashSet::new();
+            for code in &levels[pieces + 1] {
+                for n in start.with_pieces(*code).next_pieces() {
+                    next.insert(canonicalizer.canonical(n));
+                }
+            }
+            let mut level: Vec<Bits> = next.into_iter().collect();
+            level.sort_unstable();
+            levels[pieces] = level;
+        }
+
+        let mut enumeration = Enumeration {
+            shape: start.clone(),
+            canonicalizer,
+            levels,
+            winnable: vec![],
+        };
+        enumeration.mark_winnable();
+        enumeration
+    }
+
+    fn mark_winnable(&mut self) {
+        self.winnable = self.levels.iter().map(|l| vec![false; l.len()]).collect();
+        if self.levels.len() > 1 {
+            self.winnable[1].fill(true);
+        }
+        for pieces in 2..self.levels.len() {
+            let (below, this) = self.winnable.split_at_mut(pieces);
+            let below = &below[pieces - 1];
+            for (code, winnable) in self.levels[pieces].iter().zip(this[0].iter_mut()) {
+                *winnable = self
+                    .shape
+                    .with_pieces(*code)
+                    .next_pieces()
+                    .into_iter()
+                    .any(|n| {
+                        let canonical = self.canonicalizer.canonical(n);
+                        self.levels[pieces - 1]
+                            .binary_search(&canonical)
+                            .is_ok_and(|i| below[i])
+                    });
+            }
+        }
+    }
+
+    /// Counts of each level, from the start to a single piece.
+    pub fn counts(&self) -> Vec<LevelCount> {
+        (1..self.levels.len())
+            .rev()
+            .map(|pieces| {
+                let level = &self.levels[pieces];
+                let winnable = &self.winnable[pieces];
+                let orbit = |code: &Bits| self.canonicalizer.orbit_size(*code);
+                LevelCount {
+                    pieces: pieces as u32,
+                    reachable: level.iter().map(orbit).sum(),
+                    winnable: level
+                        .iter()
+                        .zip(winnable)
+                        .filter(|(_, w)| **w)
+                        .map(|(c, _)| orbit(c))
+                        .sum(),
+                    reachable_canonical: level.len() as u64,
+                    winnable_canonical: winnable.iter().filter(|w| **w).count() as u64,
+                }
+            })
+            .collect()
+    }
+
+    /// If the board can still reach a single piece, or `None` if it is not
+    /// reachable from the start.
+    pub fn winnable(&self, board: &Board) -> Option<bool> {
+        let pieces = board.count_pieces() as usize;
+        let level = self.levels.get(pieces)?;
+        let i = level
+            .binary_search(&self.canonicalizer.canonical(board.pieces()))
+            .ok()?;
+        Some(self.winnable[pieces][i])
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.shape
+    }
+
+    /// Canonical pieces of all the boards that can still reach a single
+    /// piece.
+    pub(crate) fn winnable_codes(&self) -> impl Iterator<Item = Bits> + '_ {
+        self.levels
+            .iter()
+            .zip(&self.winnable)
+            .flat_map(|(level, winnable)| level.iter().zip(winnable))
+            .filter(|(_, winnable)| **winnable)
+            .map(|(code, _)| *code)
+    }
+
+    /// Writes a line with the counts of each level.
+    pub fn write_counts<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        writeln!(
+            output,
+            "pieces reachable winnable reachable_canonical winnable_canonical"
+        )?;
+        let counts = self.counts();
+        for c in &counts {
+            writeln!(
+                output,
+                "{} {} {} {} {}",
+                c.pieces, c.reachable, c.winnable, c.reachable_canonical, c.winnable_canonical
+            )?;
+        }
+        let total = |f: fn(&LevelCount) -> u64| counts.iter().map(f).sum::<u64>();
+        writeln!(
+            output,
+            "total {} {} {} {}",
+            total(|c| c.reachable),
+            total(|c| c.winnable),
+            total(|c| c.reachable_canonical),
+            total(|c| c.winnable_canonical)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    /// Reachable boards of each level, found without symmetries.
+    fn reachable_by_level(start: &Board) -> Vec<HashSet<Board>> {
+        let mut levels = vec![HashSet::from([start.clone()])];
+        loop {
+            let next: HashSet<Board> = levels
+                .last()
+                .expect("has the start")
+                .iter()
+                .flat_map(|b| {
+                    b.find_moves()
+                        .into_iter()
+                        .map(|m| b.move_piece(m.from, m.to).expect("valid move"))
+                })
+                .collect();
+            if next.is_empty() {
+                return levels;
+            }
+            levels.push(next);
+        }
+    }
+
+    /// Checks the counts and the winnable boards against searching every
+    /// board without symmetries.
+    fn check_against_search(start: &Board) {
+        let enumeration = Enumeration::new(start);
+        let expected = reachable_by_level(start);
+        let counts = enumeration.counts();
+        assert_eq!(start.count_pieces() as usize, counts.len());
+        assert!(counts[expected.len()..].iter().all(|c| c.reachable == 0));
+        assert!(counts.iter().any(|c| c.reachable_canonical < c.reachable));
+
+        for (boards, count) in expected.iter().zip(&counts) {
+            let winnable: Vec<&Board> = boards
+                .iter()
+                .filter(|b| Solver::from_board((*b).clone()).solve().is_some())
+                .collect();
+            assert_eq!(boards.len() as u64, count.reachable);
+            assert_eq!(winnable.len() as u64, count.winnable);
+            for b in boards {
+                assert_eq!(Some(winnable.contains(&b)), enumeration.winnable(b));
+            }
+        }
+        assert_eq!(
+            None,
+            enumeration.winnable(&start.with_pieces(start.holes()))
+        );
+    }
+
+    #[test]
+    fn same_counts_as_searching_every_board() {
+        // only the mirror through the bottom left corner keeps this board
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        let corner = crate::position::Position { x: 0, y: 4 };
+        check_against_search(&full.with_pieces(full.pieces() & !crate::board::bit(corner)));
+
+        let cross: Board = "
+  ---
+  -*-
+--***--
+-*****-
+--***--
+  -*-
+  ---
+"
+        .parse()
+        .expect("valid board");
+        check_against_search(&cross);
+    }
+}
@@ -5,17 +5,160 @@
 
 use crate::board::Board;
 use crate::move_struct::Move;
+use std::fmt;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error<'a> {
     UnalinedMove(&'a str, Board, Move),
     DistanceNot2Move(&'a str, Board, Move),
     SpacesInvolvedNotCorrect(&'a str, Board, Move),
+    /// the jump at this index of a chain of jumps is not valid
+    InvalidJumpInChain(usize, Box<Error<'a>>),
+    /// a chain of jumps without any jump
+    EmptyChain,
+    /// the move at this index of a list of moves is not valid
+    InvalidMoveAt(usize, Box<Error<'a>>),
+    /// the jump increases the value of the pagoda
+    NotAPagoda(&'a str, Move),
 }
 
+/// The message followed by the move that caused it.
+impl fmt::Display for Error<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnalinedMove(message, _, m)
+            | Error::DistanceNot2Move(message, _, m)
+            | Error::SpacesInvolvedNotCorrect(message, _, m)
+            | Error::NotAPagoda(message, m) => write!(f, "{}\nMove: {}", message, m),
+            Error::InvalidJumpInChain(i, err) => write!(f, "Jump {} of the chain: {}", i, err),
+            Error::EmptyChain => write!(f, "The chain has no jumps"),
+            Error::InvalidMoveAt(i, err) => write!(f, "Move {} of the list: {}", i, err),
+        }
+    }
+}
+
+impl std::error::Error for Error<'_> {}
+
 pub const INVALID_MOVE_MESSAGE: &str = "Invalid Move. Valid Moves requre the following conditions:
 
-- Piece must start and end either in the same column or the same row.
+- Piece must start and end in the same row or column, or along another
+  direction the board allows jumps in.
 - Piece must move 2 positions, skipping over an occupied piece.
 - End position must be empty and initial position needs a piece.
 ";
+
+pub const PAGODA_MESSAGE: &str =
+    "Invalid Pagoda. The value of a pagoda can never increase with a jump:
+
+- For every jump, the weight of the end position must be at most the sum of
+  the weights of the initial and the skipped positions.
+";
+
+/// Error reading a board from a string, with the line and column (both
+/// starting at 1) where it was found.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ParseErrorKind {
+    /// only ' ', '-' and '*' are allowed
+    InvalidChar(char),
+    /// there are no rows
+    EmptyBoard,
+    /// more rows than the largest board can have
+    TooManyRows,
+    /// the row is wider than the board
+    RowTooLong,
+    /// the board does not have the expected number of rows
+    WrongSize { expected: usize, found: usize },
+    /// none of the standard boards has this number of rows
+    UnknownSize(usize),
+    /// a space is part of the board where the board has no hole
+    HoleOutsideBoard,
+    /// a space is blank where the board has a hole
+    MissingHole,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::InvalidChar(c) => {
+                write!(f, "invalid char '{}', expected ' ', '-' or '*'", c)
+            }
+            ParseErrorKind::EmptyBoard => write!(f, "the board has no rows"),
+            ParseErrorKind::TooManyRows => write!(f, "too many rows"),
+            ParseErrorKind::RowTooLong => write!(f, "row is longer than the board"),
+            ParseErrorKind::WrongSize { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            ParseErrorKind::UnknownSize(rows) => {
+                write!(f, "no standard board has {} rows", rows)
+            }
+            ParseErrorKind::HoleOutsideBoard => write!(f, "the board has no hole here"),
+            ParseErrorKind::MissingHole => write!(f, "the board has a hole here"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error reading a position or a move written in one of the notations.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NotationError {
+    /// the text is not written in the notation
+    Malformed(String),
+    /// the position is not a hole of the board
+    NotAHole(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Malformed(text) => write!(f, "can not read '{}'", text),
+            NotationError::NotAHole(text) => write!(f, "'{}' is not a hole of the board", text),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+/// Error reading a game record, or setting one of its tags.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RecordError {
+    /// the line with this number is not a tag pair
+    Malformed(usize),
+    /// a tag the record needs is missing
+    MissingTag(&'static str),
+    /// the tag with this name has a value that can not be used
+    InvalidTag(String, String),
+    /// a tag can not have this name
+    InvalidTagName(String),
+    /// the board of the tag with this name can not be read
+    InvalidBoard(String, ParseError),
+    InvalidNotation(NotationError),
+    /// a move can not be played, the error tells its index
+    InvalidMove(Box<Error<'static>>),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Malformed(line) => write!(f, "line {}: expected a tag pair", line),
+            RecordError::MissingTag(name) => write!(f, "missing tag {}", name),
+            RecordError::InvalidTag(name, value) => {
+                write!(f, "invalid value \"{}\" for tag {}", value, name)
+            }
+            RecordError::InvalidTagName(name) => write!(f, "invalid tag name \"{}\"", name),
+            RecordError::InvalidBoard(name, err) => write!(f, "tag {}, {}", name, err),
+            RecordError::InvalidNotation(err) => write!(f, "{}", err),
+            RecordError::InvalidMove(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
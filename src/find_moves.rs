@@ -1,57 +1,74 @@
 //! implements the function to find moves on the board
+//!
+//! Moves are found for all pieces at once with bit operations. For every
+//! direction the set of pieces that can jump is the pieces that have a
+//! piece next to them and an empty space after that in that direction.
 
-use crate::board::Board;
-use crate::board::Space::*;
+use crate::board::{bit, positions, Bits, Board, STRIDE};
 use crate::move_struct::Move;
 use crate::position::Position;
 
+/// Largest number of directions of any of the rules.
+const MAX_DIRECTIONS: usize = 8;
+
 impl Board {
     pub fn find_moves(&self) -> Vec<Move> {
-        let pieces = self.find_pieces();
-        let mut moves = vec![];
-        for piece in pieces.iter() {
-            for neighbor in self.neighbors(*piece).iter() {
-                let m = Move {
-                    from: *piece,
-                    to: *neighbor,
-                };
-                if self.valid_move(m).is_ok() {
-                    moves.push(m);
+        let directions = self.rules().directions();
+        let jumpers = self.jumpers();
+        let mut moves = Vec::with_capacity(16);
+        for piece in positions(jumpers.iter().fold(0, |all, j| all | j)) {
+            for ((dx, dy), j) in directions.iter().zip(jumpers.iter()) {
+                if j & bit(piece) != 0 {
+                    moves.push(Move {
+                        from: piece,
+                        to: piece + (2 * dx, 2 * dy),
+                    });
                 }
             }
         }
         moves
     }
 
-    fn neighbors(&self, p: Position) -> Vec<Position> {
-        let mut neighbors = Vec::with_capacity(4);
-        if p.x > 1 {
-            neighbors.push(Position { x: p.x - 2, y: p.y });
-        }
-        if p.x < 5 {
-            neighbors.push(Position { x: p.x + 2, y: p.y });
+    /// For each of the directions of the rules, the pieces that can jump
+    /// that way.
+    fn jumpers(&self) -> [Bits; MAX_DIRECTIONS] {
+        let (pieces, empty) = (self.pieces(), self.empty());
+        let mut jumpers = [0; MAX_DIRECTIONS];
+        for (j, (dx, dy)) in jumpers.iter_mut().zip(self.rules().directions()) {
+            let shift = dy * STRIDE as i32 + dx;
+            *j = if shift > 0 {
+                pieces & (pieces >> shift) & (empty >> (2 * shift))
+            } else {
+                pieces & (pieces << -shift) & (empty << (-2 * shift))
+            };
         }
-        if p.y > 1 {
-            neighbors.push(Position { x: p.x, y: p.y - 2 });
-        }
-        if p.y < 5 {
-            neighbors.push(Position { x: p.x, y: p.y + 2 });
-        }
-        neighbors
+        jumpers
     }
 
-    pub(crate) fn find_pieces(&self) -> Vec<Position> {
-        let mut pos = Vec::with_capacity(16);
-        for y in 0..7 {
-            for x in 0..7 {
-                let p = Position { x, y };
-                match self.at(p) {
-                    Occupied => pos.push(p),
-                    _ => (),
-                }
+    /// Pieces of the board after each of the moves, in no particular
+    /// order. Quicker than playing each of `find_moves`.
+    pub(crate) fn next_pieces(&self) -> Vec<Bits> {
+        let pieces = self.pieces();
+        let mut next = Vec::with_capacity(16);
+        for (j, (dx, dy)) in self.jumpers().iter().zip(self.rules().directions()) {
+            let shift = dy * STRIDE as i32 + dx;
+            let mut jumpers = *j;
+            while jumpers != 0 {
+                let from = jumpers & jumpers.wrapping_neg();
+                jumpers &= jumpers - 1;
+                let (over, to) = if shift > 0 {
+                    (from << shift, from << (2 * shift))
+                } else {
+                    (from >> -shift, from >> (-2 * shift))
+                };
+                next.push(pieces ^ (from | over | to));
             }
         }
-        pos
+        next
+    }
+
+    pub fn find_pieces(&self) -> Vec<Position> {
+        positions(self.pieces()).collect()
     }
 }
 
@@ -183,4 +200,91 @@ mod tests {
 
         assert_eq!(expected, got)
     }
+
+    #[test]
+    fn same_moves_as_checking_every_jump() {
+        let mut board = Board::new();
+        loop {
+            let mut expected = vec![];
+            for from in board.find_pieces() {
+                for (dx, dy) in [(-2, 0), (2, 0), (0, -2), (0, 2)] {
+                    let m = Move {
+                        from,
+                        to: from + (dx, dy),
+                    };
+                    if board.valid_move(m).is_ok() {
+                        expected.push(m);
+                    }
+                }
+            }
+            let got = board.find_moves();
+            assert_eq!(expected, got);
+
+            match got.last() {
+                Some(m) => board = board.move_piece(m.from, m.to).expect("valid move"),
+                None => break,
+            }
+        }
+    }
+
+    #[test]
+    fn triangle_move_list() {
+        let board = Board::deserialize(
+            "
+    *    
+   * -   
+  * * *  
+ - * - * 
+- * * * *
+",
+        );
+
+        let mut expected = vec![
+            Move {
+                from: Position { x: 0, y: 1 },
+                to: Position { x: 0, y: 3 },
+            },
+            Move {
+                from: Position { x: 0, y: 1 },
+                to: Position { x: 2, y: 3 },
+            },
+            Move {
+                from: Position { x: 1, y: 3 },
+                to: Position { x: 1, y: 1 },
+            },
+            Move {
+                from: Position { x: 3, y: 3 },
+                to: Position { x: 1, y: 1 },
+            },
+            Move {
+                from: Position { x: 2, y: 4 },
+                to: Position { x: 0, y: 4 },
+            },
+        ];
+
+        let mut got = board.find_moves();
+
+        expected.sort();
+        got.sort();
+
+        assert_eq!(expected, got)
+    }
+
+    #[test]
+    fn next_pieces_are_the_moves_played() {
+        let mut board = Board::french().with_rules(crate::rules::Rules::Diagonal);
+        for _ in 0..5 {
+            let mut expected: Vec<Bits> = board
+                .find_moves()
+                .iter()
+                .map(|m| board.move_piece(m.from, m.to).expect("valid move").pieces())
+                .collect();
+            let mut got = board.next_pieces();
+            expected.sort_unstable();
+            got.sort_unstable();
+            assert_eq!(expected, got);
+            let m = board.find_moves()[0];
+            board = board.move_piece(m.from, m.to).expect("valid move");
+        }
+    }
 }
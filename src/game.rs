@@ -0,0 +1,180 @@
+//! Game history
+//!
+//! A game is the starting board and the moves played on it. The cursor
+//! tells how many of those moves are applied to the current board, so
+//! undoing a move only moves the cursor back and the move can be redone
+//! until another one is played in its place.
+//!
+//! The board after each move is kept, so going to any point of the game
+//! does not replay the moves again.
+
+use crate::board::Board;
+use crate::error::Error;
+use crate::move_struct::Move;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game {
+    /// board before each move, and the board after the last one
+    boards: Vec<Board>,
+    moves: Vec<Move>,
+    /// number of moves applied to the current board
+    cursor: usize,
+}
+
+impl Game {
+    pub fn new(start: Board) -> Game {
+        Game {
+            boards: vec![start],
+            moves: vec![],
+            cursor: 0,
+        }
+    }
+
+    /// Plays all the moves, or fails with `InvalidMoveAt` the first one
+    /// that is not valid.
+    pub fn from_moves(start: Board, moves: &[Move]) -> Result<Game, Error<'static>> {
+        let boards = start.replay(moves)?;
+        Ok(Game {
+            boards,
+            moves: moves.to_vec(),
+            cursor: moves.len(),
+        })
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.boards[0]
+    }
+
+    /// Board after the moves up to the cursor.
+    pub fn board(&self) -> &Board {
+        &self.boards[self.cursor]
+    }
+
+    /// Board after the first `ply` moves.
+    pub fn board_at(&self, ply: usize) -> Option<&Board> {
+        self.boards.get(ply)
+    }
+
+    /// All the moves of the game, including the ones undone.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Moves up to the cursor.
+    pub fn played(&self) -> &[Move] {
+        &self.moves[..self.cursor]
+    }
+
+    /// Number of moves applied to the current board.
+    pub fn ply(&self) -> usize {
+        self.cursor
+    }
+
+    /// Plays a move on the current board. The moves that were undone are
+    /// dropped, unless the move is the next of them.
+    pub fn play(&mut self, m: Move) -> Result<&Board, Error<'static>> {
+        if self.moves.get(self.cursor) == Some(&m) {
+            self.cursor += 1;
+            return Ok(self.board());
+        }
+        let next = self.board().move_piece(m.from, m.to)?;
+        self.moves.truncate(self.cursor);
+        self.boards.truncate(self.cursor + 1);
+        self.moves.push(m);
+        self.boards.push(next);
+        self.cursor += 1;
+        Ok(self.board())
+    }
+
+    /// Takes back the last move played, if any.
+    pub fn undo(&mut self) -> Option<Move> {
+        if self.cursor == 0 {
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.moves[self.cursor])
+    }
+
+    /// Plays again the last move undone, if any.
+    pub fn redo(&mut self) -> Option<Move> {
+        let m = *self.moves.get(self.cursor)?;
+        self.cursor += 1;
+        Some(m)
+    }
+
+    /// Moves the cursor to after the first `ply` moves.
+    pub fn go_to(&mut self, ply: usize) -> Option<&Board> {
+        if ply > self.moves.len() {
+            return None;
+        }
+        self.cursor = ply;
+        Some(self.board())
+    }
+
+    /// A new game with the moves up to the cursor, to try other moves
+    /// without losing the ones of this game.
+    pub fn branch(&self) -> Game {
+        Game {
+            boards: self.boards[..=self.cursor].to_vec(),
+            moves: self.played().to_vec(),
+            cursor: self.cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+
+    fn jump(from: (usize, usize), to: (usize, usize)) -> Move {
+        Move {
+            from: Position {
+                x: from.0,
+                y: from.1,
+            },
+            to: Position { x: to.0, y: to.1 },
+        }
+    }
+
+    #[test]
+    fn undo_redo_and_go_to() {
+        let first = jump((3, 1), (3, 3));
+        let second = jump((1, 2), (3, 2));
+        let mut game = Game::from_moves(Board::new(), &[first, second]).expect("valid moves");
+        let end = game.board().clone();
+
+        assert_eq!(Some(second), game.undo());
+        assert_eq!(Some(first), game.undo());
+        assert_eq!(None, game.undo());
+        assert_eq!(&Board::new(), game.board());
+        assert_eq!(2, game.moves().len());
+
+        assert_eq!(Some(first), game.redo());
+        assert_eq!(&[first], game.played());
+        assert_eq!(Some(&end), game.go_to(2));
+        assert_eq!(None, game.redo());
+        assert_eq!(None, game.go_to(3));
+        assert_eq!(Some(&end), game.board_at(2));
+    }
+
+    #[test]
+    fn playing_after_undo_replaces_the_rest() {
+        let first = jump((3, 1), (3, 3));
+        let second = jump((1, 2), (3, 2));
+        let other = jump((5, 2), (3, 2));
+        let mut game = Game::from_moves(Board::new(), &[first, second]).expect("valid moves");
+
+        game.undo();
+        game.play(second).expect("valid move");
+        assert_eq!(&[first, second], game.moves());
+
+        game.undo();
+        let mut branch = game.branch();
+        game.play(other).expect("valid move");
+        assert_eq!(&[first, other], game.moves());
+        assert_eq!(&[first], branch.moves());
+        branch.play(second).expect("valid move");
+        assert_eq!(&[first, second], branch.moves());
+    }
+}
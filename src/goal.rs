@@ -0,0 +1,127 @@
+//! Goal of a game
+//!
+//! By default a game is won when a single piece is left anywhere on the
+//! board, but puzzles often ask for the last piece to end in a given
+//! space, or for a given pattern of pieces to be left.
+
+use crate::board::{bit, Board, Space};
+use crate::pagoda::Pagoda;
+use crate::position::Position;
+use crate::position_class::PositionClass;
+use crate::symmetry::Transform;
+
+#[derive(Default)]
+pub enum Goal {
+    /// a single piece left anywhere on the board
+    #[default]
+    OnePiece,
+    /// exactly this board
+    Board(Board),
+    /// any board the function accepts, it is shared by the threads of
+    /// the solver
+    Predicate(Box<dyn Fn(&Board) -> bool + Send + Sync>),
+}
+
+impl Goal {
+    /// A single piece left at `pos` on the shape of `board`.
+    ///
+    /// Panics if `pos` is not a hole of the board.
+    pub fn piece_at(board: &Board, pos: Position) -> Goal {
+        assert!(
+            board.at(pos) != Space::NotPartOfBoard,
+            "{:?} is not a hole of the board",
+            pos
+        );
+        Goal::Board(board.with_pieces(bit(pos)))
+    }
+
+    pub fn reached(&self, board: &Board) -> bool {
+        match self {
+            Goal::OnePiece => board.count_pieces() == 1,
+            Goal::Board(target) => board == target,
+            Goal::Predicate(accepts) => accepts(board),
+        }
+    }
+
+    /// True if the boards reaching the goal are the same after being
+    /// rotated or mirrored by `t`. Unknown for predicates, so only the
+    /// identity is taken as a symmetry of them.
+    pub(crate) fn symmetric_under(&self, t: Transform) -> bool {
+        match self {
+            Goal::OnePiece => true,
+            Goal::Board(target) => target.transform(t) == *target,
+            Goal::Predicate(_) => t == Transform::Identity,
+        }
+    }
+
+    /// Boards with this many pieces or less can not reach the goal,
+    /// unless they already reached it.
+    pub(crate) fn min_pieces(&self) -> u32 {
+        match self {
+            Goal::OnePiece => 1,
+            Goal::Board(target) => target.count_pieces(),
+            Goal::Predicate(_) => 0,
+        }
+    }
+
+    /// Position classes of the boards that reach the goal from `board`,
+    /// if they are known.
+    pub(crate) fn classes(&self, board: &Board) -> Option<Vec<PositionClass>> {
+        match self {
+            Goal::OnePiece => Some(board.single_piece_classes()),
+            Goal::Board(target) => Some(vec![target.position_class()]),
+            Goal::Predicate(_) => None,
+        }
+    }
+
+    /// Smallest pagoda value of the boards that reach the goal from
+    /// `board`, if it is known.
+    pub(crate) fn pagoda_target(&self, pagoda: &Pagoda, board: &Board) -> Option<i32> {
+        match self {
+            Goal::OnePiece => Some(pagoda.min_single_piece_value(board)),
+            Goal::Board(target) => Some(pagoda.value(target)),
+            Goal::Predicate(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piece_at_center() {
+        let goal = Goal::piece_at(&Board::new(), Position { x: 3, y: 3 });
+        let expected = "
+  ---  
+  ---  
+-------
+---*---
+-------
+  ---  
+  ---  
+";
+        assert!(goal.reached(&Board::deserialize(expected)));
+        assert!(!goal.reached(&Board::new()));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a hole of the board")]
+    fn piece_off_the_board() {
+        Goal::piece_at(&Board::new(), Position { x: 0, y: 0 });
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a hole of the board")]
+    fn piece_past_the_largest_board() {
+        Goal::piece_at(&Board::new(), Position { x: 20, y: 20 });
+    }
+
+    #[test]
+    fn predicate_goal() {
+        let goal = Goal::Predicate(Box::new(|b: &Board| b.count_pieces() < 32));
+        assert!(!goal.reached(&Board::new()));
+        let m = Board::new().find_moves()[0];
+        assert!(goal.reached(&Board::new().move_piece(m.from, m.to).expect("valid move")));
+    }
+}
@@ -0,0 +1,60 @@
+//! Peg solitaire
+//!
+//! Boards, rules and solvers for peg solitaire. The board is a set of
+//! holes that may hold a piece, and a move is a piece jumping over a
+//! neighbour into an empty hole, which removes the piece jumped over.
+//!
+//! The classic boards have named constructors on `Board`, and `Solver`
+//! looks for a sequence of moves that reaches a `Goal`, by default a
+//! single piece anywhere on the board.
+
+mod board;
+mod board_serde;
+mod code;
+mod database;
+mod enumerate;
+mod error;
+mod find_moves;
+mod game;
+mod goal;
+mod limits;
+mod min_moves;
+mod move_struct;
+mod notation;
+mod pagoda;
+mod parallel;
+mod play;
+mod position;
+mod position_class;
+mod record;
+mod rules;
+mod solutions;
+mod solver;
+mod stdout_render;
+mod symmetry;
+mod verify;
+
+pub use crate::board::{Board, Space};
+pub use crate::code::MAX_CODE_HOLES;
+pub use crate::database::Database;
+pub use crate::enumerate::{Enumeration, LevelCount};
+pub use crate::error::{
+    Error, NotationError, ParseError, ParseErrorKind, RecordError, INVALID_MOVE_MESSAGE,
+    PAGODA_MESSAGE,
+};
+pub use crate::game::Game;
+pub use crate::goal::Goal;
+pub use crate::limits::{Limit, Stats};
+pub use crate::move_struct::{Move, MultiJump};
+pub use crate::notation::Notation;
+pub use crate::pagoda::Pagoda;
+pub use crate::play::play;
+pub use crate::position::Position;
+pub use crate::position_class::PositionClass;
+pub use crate::record::Record;
+pub use crate::rules::Rules;
+pub use crate::solutions::Solutions;
+pub use crate::solver::{Outcome, Proof, Solver};
+pub use crate::stdout_render::print_board;
+pub use crate::symmetry::Transform;
+pub use crate::verify::Replay;
@@ -0,0 +1,176 @@
+//! Search limits
+//!
+//! A search can be given a time limit, a limit on the number of boards it
+//! searches and a cancel flag that another thread can set. When one of
+//! them is reached the solver gives up and tells what it did so far, so a
+//! program using it never waits longer than it chose to.
+//!
+//! Reading the clock on every board would slow the search down, so the
+//! time and the cancel flag are only checked every `CHECK_INTERVAL`
+//! boards.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Boards searched between two checks of the time and the cancel flag.
+pub(crate) const CHECK_INTERVAL: u64 = 1024;
+
+/// Why a search gave up.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Time,
+    Nodes,
+    Cancelled,
+}
+
+/// What a search did.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// boards searched
+    pub nodes: u64,
+    /// boards skipped because they were known to have no solution
+    pub cache_hits: u64,
+    /// boards discarded by a pagoda or the database
+    pub pruned: u64,
+    pub elapsed: Duration,
+}
+
+/// Limits of the searches of a solver.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Limits {
+    pub(crate) time: Option<Duration>,
+    pub(crate) nodes: Option<u64>,
+    pub(crate) cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Limits {
+    /// The budget of a search starting now, after the solver searched
+    /// `nodes` boards in earlier searches.
+    pub(crate) fn start(&self, nodes: u64) -> Budget {
+        let started = Instant::now();
+        Budget {
+            started,
+            start_nodes: nodes,
+            deadline: self.time.map(|time| started + time),
+            nodes: self.nodes,
+            cancel: self.cancel.clone(),
+        }
+    }
+}
+
+/// Limits of a running search.
+pub(crate) struct Budget {
+    pub(crate) started: Instant,
+    /// boards searched by the solver before this search
+    pub(crate) start_nodes: u64,
+    deadline: Option<Instant>,
+    nodes: Option<u64>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl Budget {
+    /// The limit reached once the solver searched `nodes` boards in all,
+    /// if any. Only the number of boards is checked, unless this search
+    /// went through a multiple of `CHECK_INTERVAL` boards.
+    pub(crate) fn exceeded(&self, nodes: u64) -> Option<Limit> {
+        let searched = nodes - self.start_nodes;
+        if self.nodes.is_some_and(|max| searched >= max) {
+            return Some(Limit::Nodes);
+        }
+        if !searched.is_multiple_of(CHECK_INTERVAL) {
+            return None;
+        }
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            return Some(Limit::Cancelled);
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Some(Limit::Time);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::solver::{Outcome, Solver};
+    use std::thread;
+
+    /// The French board with the d2 hole empty, that takes seconds to solve.
+    fn french_d2() -> Solver {
+        "
+  ***
+ **-**
+*******
+*******
+*******
+ *****
+  ***
+"
+        .parse()
+        .expect("valid board")
+    }
+
+    #[test]
+    fn node_limit() {
+        let mut solver = french_d2().with_node_limit(1000);
+        match solver.search() {
+            Outcome::GaveUp(Limit::Nodes, stats) => assert_eq!(1000, stats.nodes),
+            other => panic!("expected to give up, got {:?}", other),
+        }
+        // the limit is for each search
+        assert!(matches!(solver.search(), Outcome::GaveUp(Limit::Nodes, _)));
+
+        let mut solver = french_d2().with_threads(3).with_node_limit(5000);
+        match solver.search() {
+            Outcome::GaveUp(Limit::Nodes, stats) => assert!(stats.nodes >= 5000),
+            other => panic!("expected to give up, got {:?}", other),
+        }
+
+        let mut solver = Solver::from_board(Board::triangle()).with_node_limit(1_000_000);
+        assert!(solver.solve().is_some());
+    }
+
+    #[test]
+    fn time_limit() {
+        let time = Duration::from_millis(50);
+        for threads in [1, 2] {
+            let mut solver = french_d2().with_threads(threads).with_time_limit(time);
+            match solver.search() {
+                Outcome::GaveUp(Limit::Time, stats) => {
+                    assert!(stats.elapsed >= time);
+                    assert!(stats.nodes > 0);
+                }
+                other => panic!("expected to give up, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled_from_another_thread() {
+        for threads in [1, 2] {
+            let cancel = Arc::new(AtomicBool::new(false));
+            let mut solver = french_d2()
+                .with_threads(threads)
+                .with_cancel(cancel.clone());
+            let canceller = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                cancel.store(true, Ordering::Relaxed);
+            });
+            assert!(matches!(
+                solver.search(),
+                Outcome::GaveUp(Limit::Cancelled, _)
+            ));
+            canceller.join().expect("cancels");
+        }
+    }
+}
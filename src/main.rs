@@ -1,64 +1,519 @@
-mod board;
-mod board_serde;
-mod error;
-mod find_moves;
-mod move_struct;
-mod position;
-mod solver;
-mod stdout_render;
-
-use crate::board::Board;
-use crate::position::Position;
-use crate::solver::Solver;
-use crate::stdout_render::print_board;
+//! Command line interface
+//!
+//! Boards are read from a file, or from stdin when the file is `-` or
+//! missing, in the same format they are rendered in.
+//!
+//! The exit code is 0 on success, 1 when the board has no solution or the
+//! moves are not a solution, 2 when the arguments or the board can not be
+//! read, and 3 when the solver gave up before it could tell.
+
+use peg_solitaire::{
+    play, print_board, Board, Database, Enumeration, Error, Limit, Move, Outcome, ParseError,
+    ParseErrorKind, Proof, Solver,
+};
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::ExitCode;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
-fn main() {
-    let b = Board::new();
-    print_board(&b);
-    let res = b.move_piece(Position { x: 1, y: 3 }, Position { x: 3, y: 3 });
-    match res {
-        Ok(board) => print_board(&board),
-        Err(err) => println!("Error: {:?}", err),
+const USAGE: &str = "Usage: peg-solitaire <command> [args]
+
+Commands:
+  solve [file] [--database path] [--threads N] [--time-limit seconds]
+        [--node-limit N]
+                               print a solution of the board, searching
+                               with N threads, 1 by default, and giving up
+                               after the time or number of boards searched
+  validate <file> <moves>...   check that the moves solve the board
+  render [file]                print the board
+  count [file] [--symmetry]    count the solutions, with --symmetry the
+                               rotated and mirrored ones are counted once
+  bench [file] [--runs N] [--threads N]
+                               time N solves of the board, 1000 by default
+  play [file] [--database path]
+                               play a game on the board
+  enumerate [file] [--output path]
+                               count the boards reachable from the board and
+                               the ones that can still be won, per number of
+                               pieces, written to path or stdout
+  build-database [file] --output path
+                               save the boards reachable from the board
+                               that can still be won, for solve and play
+                               to skip the others with --database
+
+Boards are read from stdin when the file is - or missing, and the default
+board for bench, play, enumerate and build-database is the English board.
+A board with a number of rows no standard board has is read with the holes
+it has, spaces that are not part of the board being blank.
+Moves are written in coordinates, the column a letter and the row a number
+starting at 1 at the top, for example d2-d4.
+";
+
+const EXIT_FAILURE: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_GAVE_UP: u8 = 3;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Solve(Input, SolveOptions),
+    Validate(Input, Vec<Move>),
+    Render(Input),
+    Count(Input, bool),
+    /// board, runs, threads
+    Bench(Option<Input>, u32, usize),
+    Play(Option<Input>, Option<String>),
+    Enumerate(Option<Input>, Option<String>),
+    BuildDatabase(Option<Input>, String),
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+struct SolveOptions {
+    database: Option<String>,
+    threads: usize,
+    time_limit: Option<Duration>,
+    node_limit: Option<u64>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Input {
+    Stdin,
+    File(String),
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(run);
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(EXIT_USAGE)
+        }
     }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => return Err(USAGE.to_string()),
+    };
+    let input = |arg: Option<&String>| match arg.map(|a| a.as_str()) {
+        None | Some("-") => Input::Stdin,
+        Some(path) => Input::File(path.to_string()),
+    };
+    match command {
+        "render" => {
+            let (file, _) = file_and_options(command, rest, &[])?;
+            Ok(Command::Render(file.unwrap_or(Input::Stdin)))
+        }
+        "solve" => {
+            let names = ["--database", "--threads", "--time-limit", "--node-limit"];
+            let (file, mut options) = file_and_options(command, rest, &names)?;
+            let time_limit = number(command, &options, "--time-limit")?
+                .map(Duration::try_from_secs_f64)
+                .transpose()
+                .map_err(|_| "solve: --time-limit needs a number of seconds".to_string())?;
+            let solve_options = SolveOptions {
+                threads: number(command, &options, "--threads")?.unwrap_or(1),
+                time_limit,
+                node_limit: number(command, &options, "--node-limit")?,
+                database: options.remove("--database"),
+            };
+            Ok(Command::Solve(file.unwrap_or(Input::Stdin), solve_options))
+        }
+        "play" => {
+            let (file, mut options) = file_and_options(command, rest, &["--database"])?;
+            Ok(Command::Play(file, options.remove("--database")))
+        }
+        "validate" => {
+            let (file, moves) = rest
+                .split_first()
+                .ok_or_else(|| "validate: missing board file".to_string())?;
+            let moves = moves
+                .iter()
+                .map(|m| m.parse().map_err(|err| format!("validate: {}", err)))
+                .collect::<Result<_, _>>()?;
+            Ok(Command::Validate(input(Some(file)), moves))
+        }
+        "count" => {
+            let symmetry = rest.iter().any(|arg| arg == "--symmetry");
+            let rest: Vec<String> = rest
+                .iter()
+                .filter(|arg| *arg != "--symmetry")
+                .cloned()
+                .collect();
+            let (file, _) = file_and_options(command, &rest, &[])?;
+            Ok(Command::Count(file.unwrap_or(Input::Stdin), symmetry))
+        }
+        "bench" => {
+            let (file, options) = file_and_options(command, rest, &["--runs", "--threads"])?;
+            let runs = number(command, &options, "--runs")?.unwrap_or(1000);
+            let threads = number(command, &options, "--threads")?.unwrap_or(1);
+            Ok(Command::Bench(file, runs, threads))
+        }
+        "enumerate" => {
+            let (file, mut options) = file_and_options(command, rest, &["--output"])?;
+            Ok(Command::Enumerate(file, options.remove("--output")))
+        }
+        "build-database" => {
+            let (file, mut options) = file_and_options(command, rest, &["--output"])?;
+            match options.remove("--output") {
+                Some(output) => Ok(Command::BuildDatabase(file, output)),
+                None => Err("build-database: missing --output path".to_string()),
+            }
+        }
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("unknown command {}\n\n{}", command, USAGE)),
+    }
+}
 
-    let board = Board::deserialize(
-        "
-  ---  
-  -*-  
----*---
--------
--------
-  ---  
-  ---  
-",
-    );
-
-    print_board(&board);
-    let res = board.move_piece(Position { x: 3, y: 1 }, Position { x: 3, y: 3 });
-    match res {
-        Ok(board) => print_board(&board),
-        Err(err) => println!("Error: {:?}", err),
+/// Reads an optional board file and the options, each of them followed by
+/// its value.
+fn file_and_options(
+    command: &str,
+    rest: &[String],
+    names: &[&str],
+) -> Result<(Option<Input>, HashMap<String, String>), String> {
+    let mut args = rest.iter();
+    let mut file = None;
+    let mut options = HashMap::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            name if names.contains(&name) => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{}: {} needs a value", command, name))?;
+                options.insert(name.to_string(), value.to_string());
+            }
+            "-" if file.is_none() => file = Some(Input::Stdin),
+            arg if arg.starts_with("--") => {
+                return Err(format!("{}: unknown option {}", command, arg))
+            }
+            _ if file.is_none() => file = Some(Input::File(arg.to_string())),
+            _ => return Err(format!("{}: too many arguments", command)),
+        }
     }
+    Ok((file, options))
+}
+
+/// The value of a numeric option, if it was given.
+fn number<T: std::str::FromStr>(
+    command: &str,
+    options: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>, String> {
+    options
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("{}: {} needs a number", command, name))
+        })
+        .transpose()
+}
+
+fn read_database(path: &str) -> Result<Arc<Database>, String> {
+    Database::load(path)
+        .map(Arc::new)
+        .map_err(|err| format!("{}: {}", path, err))
+}
+
+fn read_board(input: &Input) -> Result<Board, String> {
+    let (name, text) = match input {
+        Input::Stdin => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .map_err(|err| format!("stdin: {}", err))?;
+            ("stdin", text)
+        }
+        Input::File(path) => {
+            let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+            (path.as_str(), text)
+        }
+    };
+    parse_board(name, &text)
+}
+
+/// Reads a standard board, or a board of any shape when no standard board
+/// has its number of rows. A misplaced hole on a standard board is an
+/// error, not a new shape.
+fn parse_board(name: &str, text: &str) -> Result<Board, String> {
+    text.parse()
+        .or_else(|err: ParseError| match err.kind {
+            ParseErrorKind::UnknownSize(_) => Board::parse_any_shape(text),
+            _ => Err(err),
+        })
+        .map_err(|err| format!("{}: {}", name, err))
+}
 
-    let mut solver = Solver::new();
+fn run(command: Command) -> Result<ExitCode, String> {
+    match command {
+        Command::Solve(input, options) => {
+            let mut solver = Solver::from_board(read_board(&input)?).with_threads(options.threads);
+            if let Some(path) = options.database {
+                solver = solver.with_database(read_database(&path)?);
+            }
+            if let Some(time) = options.time_limit {
+                solver = solver.with_time_limit(time);
+            }
+            if let Some(nodes) = options.node_limit {
+                solver = solver.with_node_limit(nodes);
+            }
+            match solver.search() {
+                Outcome::Solved(moves) => {
+                    for m in &moves {
+                        println!("{}", m);
+                    }
+                    Ok(ExitCode::SUCCESS)
+                }
+                Outcome::Impossible(proof) => {
+                    let reason = match proof {
+                        Proof::PositionClass => "the position class can not reach one piece",
+                        Proof::Search => "every move was searched",
+                    };
+                    println!("no solution: {}", reason);
+                    Ok(ExitCode::from(EXIT_FAILURE))
+                }
+                Outcome::GaveUp(limit, stats) => {
+                    let reason = match limit {
+                        Limit::Time => "time limit reached",
+                        Limit::Nodes => "node limit reached",
+                        Limit::Cancelled => "cancelled",
+                    };
+                    println!(
+                        "gave up: {} after {:.2?}, {} boards searched",
+                        reason, stats.elapsed, stats.nodes
+                    );
+                    Ok(ExitCode::from(EXIT_GAVE_UP))
+                }
+            }
+        }
+        Command::Validate(input, moves) => {
+            let solver = Solver::from_board(read_board(&input)?);
+            let replay = match solver.verify(&moves) {
+                Ok(replay) => replay,
+                Err(Error::InvalidMoveAt(i, err)) => {
+                    let m = moves[i];
+                    println!("move {} ({}) is not valid: {}", i + 1, m, err);
+                    return Ok(ExitCode::from(EXIT_FAILURE));
+                }
+                Err(err) => return Err(err.to_string()),
+            };
+            print_board(replay.last());
+            if replay.goal_reached {
+                println!("solved");
+                Ok(ExitCode::SUCCESS)
+            } else {
+                println!("not solved, {} pieces left", replay.last().count_pieces());
+                Ok(ExitCode::from(EXIT_FAILURE))
+            }
+        }
+        Command::Render(input) => {
+            print_board(&read_board(&input)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Count(input, symmetry) => {
+            let mut solver = Solver::from_board(read_board(&input)?);
+            let count = if symmetry {
+                solver.count_solutions_modulo_symmetry()
+            } else {
+                solver.count_solutions()
+            };
+            println!("{}", count);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Bench(input, runs, threads) => {
+            let board = match input {
+                Some(input) => read_board(&input)?,
+                None => Board::new(),
+            };
+            bench(board, runs, threads);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Play(input, database) => {
+            let database = database.as_deref().map(read_database).transpose()?;
+            let board = match input {
+                Some(Input::Stdin) => return Err("play: moves are read from stdin".to_string()),
+                Some(input) => read_board(&input)?,
+                None => Board::new(),
+            };
+            let stdin = std::io::stdin();
+            play(board, database, stdin.lock(), &mut std::io::stdout())
+                .map_err(|err| format!("play: {}", err))?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Enumerate(input, output) => {
+            let board = match input {
+                Some(input) => read_board(&input)?,
+                None => Board::new(),
+            };
+            let enumeration = Enumeration::new(&board);
+            let written = match &output {
+                Some(path) => std::fs::File::create(path)
+                    .and_then(|mut file| enumeration.write_counts(&mut file)),
+                None => enumeration.write_counts(&mut std::io::stdout()),
+            };
+            written.map_err(|err| format!("enumerate: {}", err))?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::BuildDatabase(input, output) => {
+            let board = match input {
+                Some(input) => read_board(&input)?,
+                None => Board::new(),
+            };
+            let database = Database::build(&board);
+            database
+                .save(&output)
+                .map_err(|err| format!("build-database: {}: {}", output, err))?;
+            println!("{} winnable boards saved to {}", database.len(), output);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Help => {
+            print!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
 
-    let board = Board::new();
-    let mut count = 0;
+fn bench(board: Board, runs: u32, threads: usize) {
+    let mut solver = Solver::from_board(board).with_threads(threads);
     let mut sum = Duration::new(0, 0);
-    for _i in 1..1000 {
+    for _i in 0..runs {
+        solver.clear_cache();
         let start = Instant::now();
-        let moves = solver.solve().expect("has solution");
+        let moves = solver.solve();
         let elapsed = start.elapsed();
-        count += 1;
         sum += elapsed;
-        println!("Time elapsed: {:.2?}, moves {}", elapsed, moves.len());
+        println!(
+            "Time elapsed: {:.2?}, moves {}, cache hits {}",
+            elapsed,
+            moves.map_or(0, |moves| moves.len()),
+            solver.cache_hits()
+        );
+    }
+    if runs > 0 {
+        println!("Avg Time elapsed: {:.2?}", sum / runs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use peg_solitaire::Position;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_commands() {
+        let solve = SolveOptions {
+            database: None,
+            threads: 1,
+            time_limit: None,
+            node_limit: None,
+        };
+        assert_eq!(
+            Ok(Command::Solve(Input::File("board.txt".to_string()), solve)),
+            parse_args(&args("solve board.txt"))
+        );
+        assert_eq!(
+            Ok(Command::Solve(
+                Input::Stdin,
+                SolveOptions {
+                    database: Some("english.db".to_string()),
+                    threads: 4,
+                    time_limit: Some(Duration::from_millis(1500)),
+                    node_limit: Some(1000),
+                }
+            )),
+            parse_args(&args(
+                "solve --database english.db --threads 4 --time-limit 1.5 --node-limit 1000"
+            ))
+        );
+        assert!(parse_args(&args("solve --time-limit -1")).is_err());
+        assert_eq!(
+            Ok(Command::Render(Input::Stdin)),
+            parse_args(&args("render -"))
+        );
+        assert_eq!(
+            Ok(Command::Count(Input::Stdin, true)),
+            parse_args(&args("count --symmetry"))
+        );
+        assert_eq!(
+            Ok(Command::Bench(None, 10, 1)),
+            parse_args(&args("bench --runs 10"))
+        );
+        assert!(parse_args(&args("bench --runs ten")).is_err());
+        assert_eq!(Ok(Command::Play(None, None)), parse_args(&args("play")));
+        assert_eq!(
+            Ok(Command::Enumerate(None, Some("counts.txt".to_string()))),
+            parse_args(&args("enumerate --output counts.txt"))
+        );
+        assert_eq!(
+            Ok(Command::BuildDatabase(None, "english.db".to_string())),
+            parse_args(&args("build-database --output english.db"))
+        );
+        assert!(parse_args(&args("build-database")).is_err());
+        assert!(parse_args(&args("solve a b")).is_err());
+        assert!(parse_args(&args("count a b")).is_err());
+        assert!(parse_args(&args("count --symmetry a b")).is_err());
+        assert!(parse_args(&args("count --symmetric")).is_err());
+        assert!(parse_args(&args("render a b")).is_err());
+        assert!(parse_args(&args("render --symmetry")).is_err());
+        assert!(parse_args(&args("play a b")).is_err());
+        assert!(parse_args(&args("enumerate a b")).is_err());
+        assert!(parse_args(&args("jump")).is_err());
+        assert!(parse_args(&[]).is_err());
+    }
+
+    #[test]
+    fn parse_moves() {
+        let m = Move {
+            from: Position { x: 3, y: 1 },
+            to: Position { x: 3, y: 3 },
+        };
+        assert_eq!(
+            Ok(Command::Validate(Input::Stdin, vec![m, m])),
+            parse_args(&args("validate - d2-d4 d2-d4"))
+        );
+        assert!(parse_args(&args("validate - d2")).is_err());
+        assert!(parse_args(&args("validate - d2-d")).is_err());
+    }
+
+    #[test]
+    fn parse_boards_of_any_shape() {
+        let english = parse_board("english", &Board::new().to_string()).expect("valid board");
+        assert_eq!(Board::new(), english);
+        let ring = parse_board("ring", "***\n*-*\n***\n").expect("valid board");
+        assert_eq!(8, ring.count_pieces());
+        assert_eq!(9, ring.count_holes());
+        assert!(parse_board("bad", "*x*\n").is_err());
+
+        let stray_hole = "
+  ***
+  ***
+*******
+***-***
+*******
+  ***
+ ****
+";
+        let err = parse_board("stray", stray_hole).expect_err("not the English board");
+        assert!(err.contains("line 8, column 2"), "{}", err);
+        let missing_hole = "
+  ***
+  ***
+*******
+***-***
+******
+  ***
+  ***
+";
+        assert!(parse_board("missing", missing_hole).is_err());
     }
-    //for m in moves {
-    //    print_board(&board);
-    //    board = board.move_piece(m.from, m.to).expect("valid movement");
-    // }
-    print_board(&board);
-    println!("Avg Time elapsed: {:.2?}", sum / count);
 }
@@ -0,0 +1,243 @@
+//! Minimum number of moves
+//!
+//! In the peg solitaire literature a move is a chain of jumps by the same
+//! piece, so a solution with fewer jumps chained is shorter. The search is
+//! an iterative deepening over the number of moves: the jumps of the piece
+//! that moved last are free, any other jump starts a new move.
+
+use crate::board::{Bits, Board};
+use crate::move_struct::{Move, MultiJump};
+use crate::position::Position;
+use crate::solver::Solver;
+use std::collections::HashMap;
+
+/// A board and the space where the last jump landed.
+type State = (Bits, Option<Position>);
+
+impl Solver {
+    /// Solution with the least number of moves, each move being the jumps
+    /// of a single piece.
+    pub fn solve_min_moves(&mut self) -> Option<Vec<MultiJump>> {
+        if !self.class_can_reach_goal() {
+            return None;
+        }
+        let board = self.board().clone();
+        // moves left that were not enough to reach the goal from a state
+        let mut failed: HashMap<State, u32> = HashMap::new();
+        for max_moves in 0..=board.count_pieces() {
+            let mut jumps = vec![];
+            if self.search_moves(board.clone(), None, max_moves, &mut jumps, &mut failed) {
+                return Some(MultiJump::group(&jumps));
+            }
+        }
+        None
+    }
+
+    fn search_moves(
+        &mut self,
+        board: Board,
+        last: Option<Position>,
+        moves_left: u32,
+        jumps: &mut Vec<Move>,
+        failed: &mut HashMap<State, u32>,
+    ) -> bool {
+        if self.goal().reached(&board) {
+            return true;
+        }
+        let state = (board.hash_code(), last);
+        if failed.get(&state).is_some_and(|f| *f >= moves_left) {
+            return false;
+        }
+        if self.known_dead(&board) {
+            return false;
+        }
+
+        for m in board.find_moves() {
+            let continues = last == Some(m.from);
+            if !continues && moves_left == 0 {
+                continue;
+            }
+            let left = if continues {
+                moves_left
+            } else {
+                moves_left - 1
+            };
+            let new_board = board
+                .move_piece(m.from, m.to)
+                .expect("all moves should be valid at this point");
+            jumps.push(m);
+            if self.search_moves(new_board, Some(m.to), left, jumps, failed) {
+                return true;
+            }
+            jumps.pop();
+        }
+        failed.insert(state, moves_left);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::bit;
+    use crate::goal::Goal;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    #[test]
+    fn chains_jumps_of_same_piece() {
+        let mut solver = Solver::from_str(
+            "
+  ---  
+  ---  
+-*-*---
+----*--
+----*--
+  ---  
+  ---  
+",
+        )
+        .expect("valid board");
+        let expected = vec![MultiJump {
+            from: Position { x: 4, y: 4 },
+            path: vec![
+                Position { x: 4, y: 2 },
+                Position { x: 2, y: 2 },
+                Position { x: 0, y: 2 },
+            ],
+        }];
+
+        assert_eq!(Some(expected), solver.solve_min_moves());
+    }
+
+    #[test]
+    fn fewer_moves_than_first_solution() {
+        let b_str = "
+  ---  
+  ---  
+---*---
+----*--
+----*--
+  ---  
+  ---  
+";
+        // the first solution found jumps with two different pieces
+        let first = Solver::from_str(b_str)
+            .expect("valid board")
+            .solve()
+            .expect("has a solution");
+        assert_eq!(2, MultiJump::group(&first).len());
+
+        let got = Solver::from_str(b_str)
+            .expect("valid board")
+            .solve_min_moves()
+            .expect("has a solution");
+        assert_eq!(1, got.len());
+    }
+
+    #[test]
+    fn chains_towards_predicate_goal() {
+        let board = Board::new();
+        let center = Position { x: 3, y: 3 };
+        let goal = move |board: &Board| {
+            board.count_pieces() == 30 && board.at(center) == crate::board::Space::Empty
+        };
+        let mut solver =
+            Solver::from_board(board.clone()).with_goal(Goal::Predicate(Box::new(goal)));
+        let chains = solver.solve_min_moves().expect("has a solution");
+
+        let mut replay = board;
+        for chain in chains.iter() {
+            replay = replay.apply_multi_jump(chain).expect("valid chain");
+        }
+        assert!(goal(&replay));
+        assert_eq!(2, chains.len());
+    }
+
+    /// Least number of moves to reach a single piece, found by a breadth
+    /// first search where each step plays every chain of jumps of a piece.
+    fn min_moves_by_levels(start: &Board) -> Option<usize> {
+        let mut level = HashSet::from([start.clone()]);
+        let mut seen = level.clone();
+        for moves in 0.. {
+            if level.iter().any(|b| b.count_pieces() == 1) {
+                return Some(moves);
+            }
+            let mut next = HashSet::new();
+            for board in &level {
+                let mut chains: Vec<(Board, Position)> = board
+                    .find_moves()
+                    .into_iter()
+                    .map(|m| (board.move_piece(m.from, m.to).expect("valid move"), m.to))
+                    .collect();
+                while let Some((after, piece)) = chains.pop() {
+                    for m in after.find_moves().into_iter().filter(|m| m.from == piece) {
+                        chains.push((after.move_piece(m.from, m.to).expect("valid move"), m.to));
+                    }
+                    if seen.insert(after.clone()) {
+                        next.insert(after);
+                    }
+                }
+            }
+            if next.is_empty() {
+                return None;
+            }
+            level = next;
+        }
+        None
+    }
+
+    /// The pyramid of 16 pieces, checked against a search that does not
+    /// share any code with `solve_min_moves`.
+    #[test]
+    fn pyramid_minimum_moves() {
+        let pyramid = "
+  ---
+  -*-
+--***--
+-*****-
+*******
+  ---
+  ---
+";
+        let board = Board::from_str(pyramid).expect("valid board");
+        let chains = Solver::from_board(board.clone())
+            .solve_min_moves()
+            .expect("has a solution");
+        assert_eq!(min_moves_by_levels(&board), Some(chains.len()));
+        assert_eq!(7, chains.len());
+    }
+
+    #[test]
+    fn triangle_minimum_moves() {
+        // the shortest solutions of the triangle for each of its four
+        // different empty holes, numbered 1 at the top and row by row
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        for (x, y, expected) in [(0, 0, 10), (0, 1, 10), (0, 2, 9), (1, 2, 11)] {
+            let board = full.with_pieces(full.pieces() & !bit(Position { x, y }));
+            let chains = Solver::from_board(board.clone())
+                .solve_min_moves()
+                .expect("has a solution");
+            assert_eq!(expected, chains.len(), "{}", board);
+            assert_eq!(Some(expected), min_moves_by_levels(&board), "{}", board);
+
+            let mut replay = board;
+            for chain in chains.iter() {
+                replay = replay.apply_multi_jump(chain).expect("valid chain");
+            }
+            assert_eq!(1, replay.count_pieces());
+        }
+    }
+
+    #[test]
+    #[ignore = "proving there is no shorter solution takes minutes and gigabytes"]
+    fn central_game_takes_18_moves() {
+        let board = Board::new();
+        let mut solver = Solver::new()
+            .with_goal(Goal::piece_at(&board, Position { x: 3, y: 3 }))
+            .with_pagodas(crate::pagoda::Pagoda::english())
+            .expect("valid pagodas");
+        let chains = solver.solve_min_moves().expect("has a solution");
+        assert_eq!(18, chains.len());
+    }
+}
@@ -3,12 +3,94 @@
 //! Move represents a to and a from. Its mostly just a convenience
 //! to return a list of possible Moves.
 //!
+//! MultiJump is a chain of jumps by the same piece, which is what is
+//! usually called a move when people write down a game.
+//!
 //! the file is not called move because it conflicts with the keyword.
 
 use crate::position::Position;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
 }
+
+/// A piece starting at `from` and jumping to each position of `path` in
+/// order.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MultiJump {
+    pub from: Position,
+    pub path: Vec<Position>,
+}
+
+impl MultiJump {
+    /// The single jumps of the chain.
+    pub fn jumps(&self) -> impl Iterator<Item = Move> + '_ {
+        let starts = std::iter::once(self.from).chain(self.path.iter().copied());
+        starts
+            .zip(self.path.iter())
+            .map(|(from, to)| Move { from, to: *to })
+    }
+
+    /// Groups consecutive jumps by the same piece into chains.
+    pub fn group(jumps: &[Move]) -> Vec<MultiJump> {
+        let mut chains: Vec<MultiJump> = vec![];
+        for m in jumps {
+            match chains.last_mut() {
+                Some(chain) if chain.path.last() == Some(&m.from) => chain.path.push(m.to),
+                _ => chains.push(MultiJump {
+                    from: m.from,
+                    path: vec![m.to],
+                }),
+            }
+        }
+        chains
+    }
+}
+
+impl From<Move> for MultiJump {
+    fn from(m: Move) -> MultiJump {
+        MultiJump {
+            from: m.from,
+            path: vec![m.to],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_and_split_jumps() {
+        let p = |x, y| Position { x, y };
+        let jumps = vec![
+            Move {
+                from: p(4, 4),
+                to: p(4, 2),
+            },
+            Move {
+                from: p(4, 2),
+                to: p(2, 2),
+            },
+            Move {
+                from: p(1, 2),
+                to: p(3, 2),
+            },
+        ];
+        let chains = MultiJump::group(&jumps);
+        assert_eq!(
+            vec![
+                MultiJump {
+                    from: p(4, 4),
+                    path: vec![p(4, 2), p(2, 2)],
+                },
+                MultiJump::from(jumps[2]),
+            ],
+            chains
+        );
+        let split: Vec<Move> = chains.iter().flat_map(|c| c.jumps()).collect();
+        assert_eq!(jumps, split);
+    }
+}
@@ -0,0 +1,318 @@
+//! Move notation
+//!
+//! Positions and moves can be written in the notations used in the peg
+//! solitaire literature:
+//!
+//! - Coordinates, like chess: the column is a letter starting at `a` and
+//!   the row a number starting at `1` at the top, so the center of the
+//!   English board is `d4` and `d2-d4` jumps into it from above.
+//! - Numbered holes: the holes of the board numbered from `1` in row
+//!   major order, `1` to `33` on the English board.
+//! - Grid: the column and the row as two digits starting at `1`, so the
+//!   center of the English board is `44`.
+//!
+//! The moves are the two positions joined by a `-`. Coordinates do not
+//! depend on the board, so they are what `Position` and `Move` are
+//! displayed and parsed as. The other notations need the board to check
+//! the holes and to number them.
+
+use crate::board::{positions, Board, Space, MAX_SIZE};
+use crate::error::NotationError;
+use crate::move_struct::{Move, MultiJump};
+use crate::position::Position;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// column letter and row number, `d4`
+    Coordinates,
+    /// holes numbered in row major order, `17`
+    Numbered,
+    /// column and row digits, `44`
+    Grid,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.x >= 26 {
+            // no letter for the column, only happens far outside any board
+            return write!(f, "({}, {})", self.x, self.y);
+        }
+        let column = (b'a' + self.x as u8) as char;
+        write!(f, "{}{}", column, self.y + 1)
+    }
+}
+
+impl FromStr for Position {
+    type Err = NotationError;
+
+    /// Reads a position in coordinates, like `d4`.
+    fn from_str(text: &str) -> Result<Position, NotationError> {
+        let malformed = || NotationError::Malformed(text.to_string());
+        let mut chars = text.trim().chars();
+        let column = chars.next().ok_or_else(malformed)?.to_ascii_lowercase();
+        let row: usize = chars.as_str().parse().map_err(|_| malformed())?;
+        if !column.is_ascii_lowercase() || row == 0 {
+            return Err(malformed());
+        }
+        let pos = Position {
+            x: column as usize - 'a' as usize,
+            y: row - 1,
+        };
+        if pos.x >= MAX_SIZE || pos.y >= MAX_SIZE {
+            return Err(NotationError::NotAHole(text.to_string()));
+        }
+        Ok(pos)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.from, self.to)
+    }
+}
+
+impl FromStr for Move {
+    type Err = NotationError;
+
+    /// Reads a move in coordinates, like `d2-d4`.
+    fn from_str(text: &str) -> Result<Move, NotationError> {
+        let (from, to) = split_move(text)?;
+        Ok(Move {
+            from: from.parse()?,
+            to: to.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for MultiJump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.from)?;
+        for to in &self.path {
+            write!(f, "-{}", to)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MultiJump {
+    type Err = NotationError;
+
+    /// Reads a chain of jumps in coordinates, like `d2-d4-f4`, with at
+    /// least one jump.
+    fn from_str(text: &str) -> Result<MultiJump, NotationError> {
+        let mut positions = text.trim().split('-').map(str::parse);
+        let from = positions
+            .next()
+            .ok_or_else(|| NotationError::Malformed(text.to_string()))??;
+        let path = positions.collect::<Result<Vec<Position>, _>>()?;
+        if path.is_empty() {
+            return Err(NotationError::Malformed(text.to_string()));
+        }
+        Ok(MultiJump { from, path })
+    }
+}
+
+fn split_move(text: &str) -> Result<(&str, &str), NotationError> {
+    text.trim()
+        .split_once('-')
+        .ok_or_else(|| NotationError::Malformed(text.to_string()))
+}
+
+/// Positions and moves in the notations, checked against the holes of
+/// the board.
+impl Board {
+    /// The position written in the notation, or `None` if it is not a
+    /// hole of the board.
+    pub fn format_position(&self, pos: Position, notation: Notation) -> Option<String> {
+        if self.at(pos) == Space::NotPartOfBoard {
+            return None;
+        }
+        match notation {
+            Notation::Coordinates => Some(pos.to_string()),
+            Notation::Numbered => {
+                let number = positions(self.holes()).position(|hole| hole == pos)? + 1;
+                Some(number.to_string())
+            }
+            Notation::Grid => Some(format!("{}{}", pos.x + 1, pos.y + 1)),
+        }
+    }
+
+    /// Reads a position in the notation, that must be a hole of the
+    /// board.
+    pub fn parse_position(
+        &self,
+        text: &str,
+        notation: Notation,
+    ) -> Result<Position, NotationError> {
+        let malformed = || NotationError::Malformed(text.to_string());
+        let not_a_hole = || NotationError::NotAHole(text.to_string());
+        let trimmed = text.trim();
+        let pos = match notation {
+            Notation::Coordinates => trimmed.parse::<Position>()?,
+            Notation::Numbered => {
+                let number: usize = trimmed.parse().map_err(|_| malformed())?;
+                let index = number.checked_sub(1).ok_or_else(not_a_hole)?;
+                positions(self.holes()).nth(index).ok_or_else(not_a_hole)?
+            }
+            Notation::Grid => {
+                let digits: Vec<usize> = trimmed
+                    .chars()
+                    .map(|c| c.to_digit(10).map(|d| d as usize))
+                    .collect::<Option<_>>()
+                    .ok_or_else(malformed)?;
+                match digits.as_slice() {
+                    [0, _] | [_, 0] => return Err(not_a_hole()),
+                    [column, row] => Position {
+                        x: column - 1,
+                        y: row - 1,
+                    },
+                    _ => return Err(malformed()),
+                }
+            }
+        };
+        if self.at(pos) == Space::NotPartOfBoard {
+            return Err(not_a_hole());
+        }
+        Ok(pos)
+    }
+
+    /// The move written in the notation, or `None` if one of its positions
+    /// is not a hole of the board.
+    pub fn format_move(&self, m: Move, notation: Notation) -> Option<String> {
+        Some(format!(
+            "{}-{}",
+            self.format_position(m.from, notation)?,
+            self.format_position(m.to, notation)?
+        ))
+    }
+
+    /// Reads a move in the notation. Only the positions are checked, not
+    /// that the move is valid.
+    pub fn parse_move(&self, text: &str, notation: Notation) -> Result<Move, NotationError> {
+        let (from, to) = split_move(text)?;
+        Ok(Move {
+            from: self.parse_position(from, notation)?,
+            to: self.parse_position(to, notation)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTATIONS: [Notation; 3] = [Notation::Coordinates, Notation::Numbered, Notation::Grid];
+
+    #[test]
+    fn central_game_first_move() {
+        let board = Board::new();
+        let m = Move {
+            from: Position { x: 3, y: 1 },
+            to: Position { x: 3, y: 3 },
+        };
+        assert_eq!("d2-d4", m.to_string());
+        assert_eq!(Ok(m), "d2-d4".parse());
+        assert_eq!(
+            Some("5-17".to_string()),
+            board.format_move(m, Notation::Numbered)
+        );
+        assert_eq!(
+            Some("42-44".to_string()),
+            board.format_move(m, Notation::Grid)
+        );
+        assert_eq!(Ok(m), board.parse_move("5-17", Notation::Numbered));
+        assert_eq!(Ok(m), board.parse_move("42-44", Notation::Grid));
+    }
+
+    #[test]
+    fn multi_jump_round_trips() {
+        let chain = MultiJump {
+            from: Position { x: 4, y: 4 },
+            path: vec![Position { x: 4, y: 2 }, Position { x: 2, y: 2 }],
+        };
+        assert_eq!("e5-e3-c3", chain.to_string());
+        assert_eq!(Ok(chain.clone()), chain.to_string().parse());
+        let single = MultiJump::from(Move {
+            from: Position { x: 3, y: 1 },
+            to: Position { x: 3, y: 3 },
+        });
+        assert_eq!(Ok(single), "d2-d4".parse());
+
+        let malformed = |text: &str| Err(NotationError::Malformed(text.to_string()));
+        assert_eq!(malformed("d2"), "d2".parse::<MultiJump>());
+        assert_eq!(malformed(""), "".parse::<MultiJump>());
+        assert_eq!(malformed("x"), "d2-d4-x".parse::<MultiJump>());
+    }
+
+    #[test]
+    fn every_hole_round_trips() {
+        for (name, board) in Board::standard() {
+            for pos in positions(board.holes()) {
+                for notation in NOTATIONS {
+                    let text = board.format_position(pos, notation).expect(name);
+                    assert_eq!(Ok(pos), board.parse_position(&text, notation), "{}", text);
+                }
+            }
+        }
+        let numbers: Vec<String> = positions(Board::new().holes())
+            .map(|pos| {
+                Board::new()
+                    .format_position(pos, Notation::Numbered)
+                    .expect("hole")
+            })
+            .collect();
+        assert_eq!("1", numbers[0]);
+        assert_eq!("33", numbers[32]);
+    }
+
+    #[test]
+    fn positions_outside_the_board() {
+        let board = Board::new();
+        let corner = Position { x: 0, y: 0 };
+        for notation in NOTATIONS {
+            assert_eq!(None, board.format_position(corner, notation));
+        }
+        let not_a_hole = |text: &str| Err(NotationError::NotAHole(text.to_string()));
+        assert_eq!(
+            not_a_hole("a1"),
+            board.parse_position("a1", Notation::Coordinates)
+        );
+        assert_eq!(
+            not_a_hole("34"),
+            board.parse_position("34", Notation::Numbered)
+        );
+        assert_eq!(
+            not_a_hole("0"),
+            board.parse_position("0", Notation::Numbered)
+        );
+        assert_eq!(not_a_hole("11"), board.parse_position("11", Notation::Grid));
+        assert_eq!(not_a_hole("80"), board.parse_position("80", Notation::Grid));
+        assert_eq!(
+            not_a_hole("h4"),
+            board.parse_position("h4", Notation::Coordinates)
+        );
+    }
+
+    #[test]
+    fn malformed_notation() {
+        let board = Board::new();
+        let malformed = |text: &str| Err(NotationError::Malformed(text.to_string()));
+        assert_eq!(malformed("d"), "d".parse::<Position>());
+        assert_eq!(malformed("4d"), "4d".parse::<Position>());
+        assert_eq!(malformed("d0"), "d0".parse::<Position>());
+        assert_eq!(
+            Err(NotationError::Malformed("d2d4".to_string())),
+            "d2d4".parse::<Move>()
+        );
+        assert_eq!(
+            malformed("444"),
+            board.parse_position("444", Notation::Grid)
+        );
+        assert_eq!(
+            malformed("x"),
+            board.parse_position("x", Notation::Numbered)
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Pagoda functions
+//!
+//! A pagoda function gives a weight to each space of the board. The value
+//! of a board is the sum of the weights of the spaces holding a piece. The
+//! weights are chosen so that no jump can increase the value: for every
+//! jump the weight of the end position is at most the weight of the
+//! initial position plus the weight of the skipped position.
+//!
+//! So if a board has a smaller value than the target board, the target
+//! can never be reached from it and the solver does not need to search
+//! it.
+
+use crate::board::Space::NotPartOfBoard;
+use crate::board::{positions, Board};
+use crate::error::{Error, PAGODA_MESSAGE};
+use crate::move_struct::Move;
+use crate::position::Position;
+use crate::symmetry::Transform;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pagoda {
+    /// weights by row
+    weights: Vec<Vec<i32>>,
+}
+
+impl Pagoda {
+    pub fn from_array<const N: usize>(weights: [[i32; N]; N]) -> Pagoda {
+        Pagoda {
+            weights: weights.iter().map(|row| row.to_vec()).collect(),
+        }
+    }
+
+    pub fn weight(&self, pos: Position) -> i32 {
+        match self.weights.get(pos.y).and_then(|row| row.get(pos.x)) {
+            Some(w) => *w,
+            None => 0,
+        }
+    }
+
+    /// Sum of the weights of the spaces holding a piece.
+    pub fn value(&self, board: &Board) -> i32 {
+        positions(board.pieces()).map(|p| self.weight(p)).sum()
+    }
+
+    /// Smallest value a board with a single piece can have.
+    pub fn min_single_piece_value(&self, board: &Board) -> i32 {
+        positions(board.holes())
+            .map(|p| self.weight(p))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Checks that no jump on the shape of `board` can increase the value,
+    /// returning the first jump that does.
+    pub fn validate(&self, board: &Board) -> Result<(), Error<'static>> {
+        for from in positions(board.holes()) {
+            for step in board.rules().directions() {
+                let movement = Move {
+                    from,
+                    to: from + (2 * step.0, 2 * step.1),
+                };
+                let middle = from + *step;
+                if board.at(movement.to) == NotPartOfBoard || board.at(middle) == NotPartOfBoard {
+                    continue;
+                }
+                if self.weight(from) + self.weight(middle) < self.weight(movement.to) {
+                    return Err(Error::NotAPagoda(PAGODA_MESSAGE, movement));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The pagoda rotated or mirrored by `t`.
+    pub fn transform(&self, t: Transform) -> Pagoda {
+        let size = self.weights.len();
+        let mut weights = vec![vec![0; size]; size];
+        for (y, row) in self.weights.iter().enumerate() {
+            for (x, w) in row.iter().enumerate() {
+                let p = t.apply_position(Position { x, y }, size);
+                weights[p.y][p.x] = *w;
+            }
+        }
+        Pagoda { weights }
+    }
+
+    /// Standard pagodas of the English board.
+    ///
+    /// The first one weights the holes where both coordinates are odd, a
+    /// jump into one of them always starts from another one. The other
+    /// four add the tip of one of the arms to it, a jump into the tip
+    /// always skips over one of the odd holes.
+    pub fn english() -> Vec<Pagoda> {
+        #[rustfmt::skip]
+        let odd = Pagoda::from_array([
+            [0, 0, 0, 0, 0, 0, 0],
+            [0, 0, 0, 1, 0, 0, 0],
+            [0, 0, 0, 0, 0, 0, 0],
+            [0, 1, 0, 1, 0, 1, 0],
+            [0, 0, 0, 0, 0, 0, 0],
+            [0, 0, 0, 1, 0, 0, 0],
+            [0, 0, 0, 0, 0, 0, 0],
+        ]);
+        #[rustfmt::skip]
+        let arm = Pagoda::from_array([
+            [0, 0, 0, 1, 0, 0, 0],
+            [0, 0, 0, 1, 0, 0, 0],
+            [0, 0, 0, 0, 0, 0, 0],
+            [0, 1, 0, 1, 0, 1, 0],
+            [0, 0, 0, 0, 0, 0, 0],
+            [0, 0, 0, 1, 0, 0, 0],
+            [0, 0, 0, 0, 0, 0, 0],
+        ]);
+        let mut pagodas = vec![odd];
+        for t in [
+            Transform::Identity,
+            Transform::Rotate90,
+            Transform::Rotate180,
+            Transform::Rotate270,
+        ] {
+            pagodas.push(arm.transform(t));
+        }
+        pagodas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn english_pagodas_are_valid() {
+        for pagoda in Pagoda::english() {
+            assert_eq!(Ok(()), pagoda.validate(&Board::new()));
+        }
+    }
+
+    #[test]
+    fn invalid_pagoda_reports_jump() {
+        let mut weights = [[0; 7]; 7];
+        weights[0][3] = 1;
+        let pagoda = Pagoda::from_array(weights);
+
+        assert_eq!(
+            Err(Error::NotAPagoda(
+                PAGODA_MESSAGE,
+                Move {
+                    from: Position { x: 3, y: 2 },
+                    to: Position { x: 3, y: 0 },
+                }
+            )),
+            pagoda.validate(&Board::new())
+        );
+    }
+
+    #[test]
+    fn value_never_increases() {
+        let pagodas = Pagoda::english();
+        let mut board = Board::new();
+        while let Some(m) = board.find_moves().first() {
+            let next = board.move_piece(m.from, m.to).expect("valid move");
+            for pagoda in pagodas.iter() {
+                assert!(pagoda.value(&next) <= pagoda.value(&board));
+            }
+            board = next;
+        }
+    }
+}
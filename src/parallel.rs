@@ -0,0 +1,327 @@
+//! Parallel search
+//!
+//! The same depth first search as the solver, run by several threads. The
+//! boards still to be searched are kept in a shared queue that starts with
+//! the board of the solver. A thread takes a board from the queue and
+//! searches it alone until it sees another thread waiting for work, then
+//! it hands the moves it has not tried yet at its current board over to
+//! the queue.
+//!
+//! The positions found to have no solution are shared by all the threads,
+//! in a set split in shards so they seldom wait on each other. The first
+//! thread to find a solution or to reach a limit of the search stops the
+//! others.
+
+use crate::board::{Bits, Board};
+use crate::limits::{Budget, Limit, CHECK_INTERVAL};
+use crate::move_struct::Move;
+use crate::solver::Solver;
+use std::collections::HashSet;
+use std::ops::AddAssign;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// Number of parts of the set of dead positions, each with its own lock.
+const SHARDS: usize = 64;
+
+/// Boards with fewer pieces are searched by the thread that reaches them,
+/// handing them over would take longer than searching them.
+const MIN_SPLIT_PIECES: u32 = 10;
+
+/// What the threads did, added up.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct Counts {
+    /// boards searched
+    pub(crate) nodes: u64,
+    pub(crate) cache_hits: u64,
+    pub(crate) pruned: u64,
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        self.nodes += other.nodes;
+        self.cache_hits += other.cache_hits;
+        self.pruned += other.pruned;
+    }
+}
+
+/// A board to search and the moves that reach it from the start.
+struct Task {
+    board: Board,
+    path: Vec<Move>,
+}
+
+struct Queue {
+    tasks: Vec<Task>,
+    /// tasks in the queue or being searched, the search is over when there
+    /// are none left
+    pending: usize,
+}
+
+struct Search<'a> {
+    solver: &'a Solver,
+    budget: &'a Budget,
+    /// boards searched by the solver, updated by each thread every
+    /// `CHECK_INTERVAL` boards it searches
+    nodes: AtomicU64,
+    gave_up: Mutex<Option<Limit>>,
+    queue: Mutex<Queue>,
+    changed: Condvar,
+    /// threads that started waiting for a task since the last boards were
+    /// handed over
+    idle: AtomicUsize,
+    done: AtomicBool,
+    solution: Mutex<Option<Vec<Move>>>,
+    dead_positions: Vec<Mutex<HashSet<Bits>>>,
+}
+
+/// Searches the board of the solver with `threads` threads, returns the
+/// solution if one was found and the limit reached if the search gave up.
+pub(crate) fn search(
+    solver: &Solver,
+    threads: usize,
+    budget: &Budget,
+) -> (Option<Vec<Move>>, Counts, Option<Limit>) {
+    let search = Search {
+        solver,
+        budget,
+        nodes: AtomicU64::new(budget.start_nodes),
+        gave_up: Mutex::new(None),
+        queue: Mutex::new(Queue {
+            tasks: vec![Task {
+                board: solver.board().clone(),
+                path: Vec::with_capacity(32),
+            }],
+            pending: 1,
+        }),
+        changed: Condvar::new(),
+        idle: AtomicUsize::new(0),
+        done: AtomicBool::new(false),
+        solution: Mutex::new(None),
+        dead_positions: (0..SHARDS).map(|_| Mutex::new(HashSet::new())).collect(),
+    };
+    let counts = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| scope.spawn(|| search.work()))
+            .collect();
+        let mut counts = Counts::default();
+        for worker in workers {
+            counts += worker.join().expect("search thread panicked");
+        }
+        counts
+    });
+    let solution = lock(&search.solution).take();
+    let gave_up = lock(&search.gave_up).take();
+    (solution, counts, gave_up)
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().expect("search thread panicked")
+}
+
+impl Search<'_> {
+    /// Searches tasks until there are none left or a solution is found.
+    fn work(&self) -> Counts {
+        let mut counts = Counts::default();
+        while let Some(mut task) = self.next_task() {
+            self.search(&task.board, &mut task.path, &mut counts);
+            let mut queue = lock(&self.queue);
+            queue.pending -= 1;
+            if queue.pending == 0 {
+                self.changed.notify_all();
+            }
+        }
+        counts
+    }
+
+    /// Waits for a task, or `None` when the search is over.
+    fn next_task(&self) -> Option<Task> {
+        let mut queue = lock(&self.queue);
+        loop {
+            if self.done.load(Ordering::Relaxed) || queue.pending == 0 {
+                return None;
+            }
+            if let Some(task) = queue.tasks.pop() {
+                return Some(task);
+            }
+            self.idle.fetch_add(1, Ordering::Relaxed);
+            queue = self.changed.wait(queue).expect("search thread panicked");
+        }
+    }
+
+    /// Puts the boards after each of the moves in the queue, the first
+    /// move on top so the moves are still tried about in order.
+    fn hand_over(&self, board: &Board, path: &[Move], moves: Vec<Move>) {
+        let tasks = moves.into_iter().rev().map(|m| {
+            let mut path = path.to_vec();
+            path.push(m);
+            Task {
+                board: board
+                    .move_piece(m.from, m.to)
+                    .expect("all moves should be valid at this point"),
+                path,
+            }
+        });
+        let mut queue = lock(&self.queue);
+        let before = queue.tasks.len();
+        queue.tasks.extend(tasks);
+        queue.pending += queue.tasks.len() - before;
+        self.changed.notify_all();
+    }
+
+    fn stop(&self, solution: &[Move]) {
+        lock(&self.solution).get_or_insert_with(|| solution.to_vec());
+        self.stop_all();
+    }
+
+    fn give_up(&self, limit: Limit) {
+        lock(&self.gave_up).get_or_insert(limit);
+        self.stop_all();
+    }
+
+    fn stop_all(&self) {
+        self.done.store(true, Ordering::Relaxed);
+        let _queue = lock(&self.queue);
+        self.changed.notify_all();
+    }
+
+    /// True if a solution was found from `board`, `path` holds the moves
+    /// that reach it.
+    fn search(&self, board: &Board, path: &mut Vec<Move>, counts: &mut Counts) -> bool {
+        if self.done.load(Ordering::Relaxed) {
+            return false;
+        }
+        if self.solver.goal().reached(board) {
+            self.stop(path);
+            return true;
+        }
+        if self.known_dead(board, counts) {
+            return false;
+        }
+        if counts.nodes.is_multiple_of(CHECK_INTERVAL) {
+            let nodes = self.nodes.fetch_add(CHECK_INTERVAL, Ordering::Relaxed) + CHECK_INTERVAL;
+            if let Some(limit) = self.budget.exceeded(nodes) {
+                self.give_up(limit);
+                return false;
+            }
+        }
+
+        let mut moves = board.find_moves();
+        let mut searched_all = true;
+        let mut i = 0;
+        while i < moves.len() {
+            if i + 1 < moves.len()
+                && board.count_pieces() >= MIN_SPLIT_PIECES
+                && self.idle.load(Ordering::Relaxed) > 0
+                && self.idle.swap(0, Ordering::Relaxed) > 0
+            {
+                let rest = moves.split_off(i + 1);
+                self.hand_over(board, path, rest);
+                searched_all = false;
+            }
+            let m = moves[i];
+            let new_board = board
+                .move_piece(m.from, m.to)
+                .expect("all moves should be valid at this point");
+            path.push(m);
+            if self.search(&new_board, path, counts) {
+                return true;
+            }
+            path.pop();
+            i += 1;
+        }
+        // a board whose search was cut short may still have a solution
+        if searched_all && !self.done.load(Ordering::Relaxed) {
+            self.shard(board.hash_code()).insert(board.hash_code());
+        }
+        false
+    }
+
+    /// Same as `Solver::known_dead`, with the shared positions.
+    fn known_dead(&self, board: &Board, counts: &mut Counts) -> bool {
+        if board.count_pieces() <= self.solver.goal().min_pieces() {
+            return true;
+        }
+        let code = board.hash_code();
+        if self.shard(code).contains(&code) {
+            counts.cache_hits += 1;
+            return true;
+        }
+        counts.nodes += 1;
+        if self.solver.can_prune(board) {
+            counts.pruned += 1;
+            self.shard(code).insert(code);
+            return true;
+        }
+        false
+    }
+
+    fn shard(&self, code: Bits) -> MutexGuard<'_, HashSet<Bits>> {
+        let mixed = (code as u64 ^ (code >> 64) as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        lock(&self.dead_positions[(mixed >> 58) as usize % SHARDS])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::goal::Goal;
+    use crate::limits::Limits;
+    use crate::pagoda::Pagoda;
+    use crate::position::Position;
+
+    #[test]
+    fn same_outcome_as_one_thread() {
+        let cross: Board = "
+  ---
+  -*-
+--***--
+-*****-
+--***--
+  -*-
+  ---
+"
+        .parse()
+        .expect("valid board");
+        // a predicate skips the position class check, so the cross is
+        // only found impossible by searching all of it
+        let one_piece = || Goal::Predicate(Box::new(|b: &Board| b.count_pieces() == 1));
+        for board in [Board::new(), Board::triangle(), cross] {
+            let expected = Solver::from_board(board.clone())
+                .with_goal(one_piece())
+                .solve()
+                .is_some();
+            for threads in [2, 4] {
+                let mut solver = Solver::from_board(board.clone())
+                    .with_goal(one_piece())
+                    .with_threads(threads);
+                match solver.solve() {
+                    Some(moves) => {
+                        assert!(solver.verify(&moves).expect("valid moves").goal_reached)
+                    }
+                    None => assert!(!expected, "{}", board),
+                }
+                assert!(solver.cache_hits() > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn central_game() {
+        let board = Board::new();
+        let center = Position { x: 3, y: 3 };
+        let mut solver = Solver::new()
+            .with_goal(Goal::piece_at(&board, center))
+            .with_pagodas(Pagoda::english())
+            .expect("valid pagodas")
+            .with_threads(3);
+        let budget = Limits::default().start(0);
+        let (solution, counts, gave_up) = search(&solver, 3, &budget);
+        assert_eq!(None, gave_up);
+        let solution = solution.expect("the central game has a solution");
+        assert!(solver.verify(&solution).expect("valid moves").goal_reached);
+        assert!(counts.nodes > 0);
+        assert!(solver.solve().is_some());
+    }
+}
@@ -0,0 +1,269 @@
+//! Interactive game
+//!
+//! Plays a game reading commands from an input and writing the board and
+//! messages to an output, so it works on a terminal and in tests alike.
+//! Moves are typed in coordinates, `d2-d4` or `d2 d4`, with the column
+//! letters and row numbers that are printed around the board.
+//!
+//! Hints come from the solver, which answers at once when it is given a
+//! database of winnable boards, and gives up after `HINT_TIME_LIMIT`
+//! otherwise.
+
+use crate::board::{Board, Space};
+use crate::database::Database;
+use crate::error::{Error, NotationError};
+use crate::game::Game;
+use crate::move_struct::Move;
+use crate::notation::Notation;
+use crate::position::Position;
+use crate::solver::{Outcome, Solver};
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Time the solver searches for a hint before giving up.
+const HINT_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+const HELP: &str = "Commands:
+  d2 d4     move the piece at the first position to the second one
+  undo      take back the last move
+  redo      play again the last move taken back
+  hint      show a move that still leads to a solution
+  help      show this message
+  quit      end the game
+";
+
+/// Plays a game starting at `board` until the input ends or the player
+/// quits, using the database for hints if there is one.
+pub fn play<R: BufRead, W: Write>(
+    board: Board,
+    database: Option<Arc<Database>>,
+    input: R,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut game = Game::new(board);
+    write!(output, "{}", HELP)?;
+    show(game.board(), output)?;
+
+    for line in input.lines() {
+        let line = line?;
+        match line.trim() {
+            "" => continue,
+            "quit" | "q" => break,
+            "help" | "?" => write!(output, "{}", HELP)?,
+            "undo" | "u" => match game.undo() {
+                Some(_) => show(game.board(), output)?,
+                None => writeln!(output, "Nothing to undo.")?,
+            },
+            "redo" | "r" => match game.redo() {
+                Some(_) => show(game.board(), output)?,
+                None => writeln!(output, "Nothing to redo.")?,
+            },
+            "hint" | "h" => match hint_solver(game.board(), &database).search() {
+                Outcome::Solved(moves) => match moves.first() {
+                    Some(m) => writeln!(output, "Try {}.", m)?,
+                    None => writeln!(output, "The game is already won.")?,
+                },
+                Outcome::Impossible(_) => {
+                    writeln!(output, "There is no solution from here, try undo.")?
+                }
+                Outcome::GaveUp(..) => writeln!(output, "No hint found in time.")?,
+            },
+            command => match parse_move(game.board(), command) {
+                Err(NotationError::Malformed(_)) => writeln!(
+                    output,
+                    "Unknown command {}, type help to see the commands.",
+                    command
+                )?,
+                Err(err) => writeln!(output, "{}.", capitalize(&err.to_string()))?,
+                Ok(m) => match game.play(m) {
+                    Ok(board) => show(board, output)?,
+                    Err(err) => writeln!(output, "{}.", describe(&err))?,
+                },
+            },
+        }
+    }
+    Ok(())
+}
+
+fn hint_solver(board: &Board, database: &Option<Arc<Database>>) -> Solver {
+    let solver = Solver::from_board(board.clone()).with_time_limit(HINT_TIME_LIMIT);
+    match database {
+        Some(database) => solver.with_database(database.clone()),
+        None => solver,
+    }
+}
+
+/// Writes the board with the coordinates of each row and column, and tells
+/// when the game is over.
+fn show<W: Write>(board: &Board, output: &mut W) -> io::Result<()> {
+    write!(output, "\n  ")?;
+    for x in 0..board.size() {
+        write!(output, " {}", (b'a' + x as u8) as char)?;
+    }
+    writeln!(output)?;
+    for y in 0..board.size() {
+        let mut row = format!("{} ", y + 1);
+        for x in 0..board.size() {
+            row.push(' ');
+            row.push(match board.at(Position { x, y }) {
+                Space::Empty => '-',
+                Space::Occupied => '*',
+                Space::NotPartOfBoard => ' ',
+            });
+        }
+        writeln!(output, "{}", row.trim_end())?;
+    }
+    writeln!(output)?;
+
+    if board.count_pieces() == 1 {
+        writeln!(output, "You won, a single piece is left!")?;
+    } else if board.find_moves().is_empty() {
+        writeln!(
+            output,
+            "Game over, no moves left with {} pieces on the board. Type undo to go back.",
+            board.count_pieces()
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads `d2-d4` or `d2 d4`.
+fn parse_move(board: &Board, text: &str) -> Result<Move, NotationError> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join("-");
+    board.parse_move(&text, Notation::Coordinates)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Explains in a sentence why a move is not valid.
+fn describe(err: &Error) -> String {
+    match err {
+        Error::UnalinedMove(_, _, m) => format!(
+            "{} and {} are not in a line a piece can jump along",
+            m.from, m.to
+        ),
+        Error::DistanceNot2Move(_, _, _) => {
+            "A piece must jump over exactly one space, landing two spaces away".to_string()
+        }
+        Error::SpacesInvolvedNotCorrect(_, board, m) => {
+            let middle = Position {
+                x: (m.from.x + m.to.x) / 2,
+                y: (m.from.y + m.to.y) / 2,
+            };
+            let (from, to) = (m.from, m.to);
+            match (board.at(m.from), board.at(middle), board.at(m.to)) {
+                (Space::NotPartOfBoard, _, _) => format!("{} is not part of the board", from),
+                (Space::Empty, _, _) => format!("There is no piece at {}", from),
+                (_, _, Space::NotPartOfBoard) => format!("{} is not part of the board", to),
+                (_, _, Space::Occupied) => format!("{} already holds a piece", to),
+                _ => format!("There is no piece to jump over at {}", middle),
+            }
+        }
+        _ => err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play_lines(board: Board, lines: &str) -> String {
+        let mut output = vec![];
+        play(board, None, lines.as_bytes(), &mut output).expect("writes to a vec");
+        String::from_utf8(output).expect("utf8 output")
+    }
+
+    #[test]
+    fn win_a_game() {
+        let board: Board = "
+  ---
+  ---
+-------
+----*--
+----*--
+  ---
+  ---
+"
+        .parse()
+        .expect("valid board");
+        let output = play_lines(board, "hint\ne4 e6\n");
+        assert!(output.contains("Try e4-e6."), "{}", output);
+        assert!(output.contains("You won"), "{}", output);
+    }
+
+    #[test]
+    fn hint_on_a_won_game() {
+        let board: Board = "
+  ---
+  ---
+-------
+---*---
+-------
+  ---
+  ---
+"
+        .parse()
+        .expect("valid board");
+        let output = play_lines(board, "hint\n");
+        assert!(output.contains("The game is already won."), "{}", output);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let output = play_lines(Board::new(), "undo\nd2-d4\nundo\nredo\nredo\n");
+        assert!(output.contains("Nothing to undo."), "{}", output);
+        assert!(output.contains("Nothing to redo."), "{}", output);
+        let boards: Vec<Vec<&str>> = output
+            .split("   a b c d e f g\n")
+            .skip(1)
+            .map(|shown| shown.lines().take(7).collect())
+            .collect();
+        assert_eq!(4, boards.len());
+        assert_eq!(boards[0], boards[2]);
+        assert_eq!(boards[1], boards[3]);
+        assert_ne!(boards[0], boards[1]);
+    }
+
+    #[test]
+    fn friendly_errors() {
+        let output = play_lines(Board::new(), "a1 a3\nd4 d2\nd2 d3\nd2 f4\nc3 c5\nfoo\n");
+        assert!(
+            output.contains("'a1' is not a hole of the board."),
+            "{}",
+            output
+        );
+        assert!(output.contains("There is no piece at d4."), "{}", output);
+        assert!(
+            output.contains("A piece must jump over exactly one space"),
+            "{}",
+            output
+        );
+        assert!(output.contains("d2 and f4 are not in a line"), "{}", output);
+        assert!(output.contains("c5 already holds a piece."), "{}", output);
+        assert!(output.contains("Unknown command foo"), "{}", output);
+    }
+
+    #[test]
+    fn game_over_without_moves() {
+        let board: Board = "
+  *--
+  ---
+-------
+-------
+-------
+  ---
+  --*
+"
+        .parse()
+        .expect("valid board");
+        let output = play_lines(board, "");
+        assert!(output.contains("no moves left with 2 pieces"), "{}", output);
+    }
+}
@@ -0,0 +1,115 @@
+//! Position classes
+//!
+//! Color the board diagonally with three colors, so that the three spaces
+//! involved in any jump have one space of each color. A jump removes a
+//! piece from two colors and adds one to the third, so it flips the parity
+//! of the number of pieces of every color. Whether two colors have the
+//! same parity can never change.
+//!
+//! On the square boards there are two such colorings, one along each
+//! diagonal, each giving two bits that never change. The 4 bits split the
+//! boards in 16 position classes, and a board can only reach boards in
+//! its own class. Other rules allow fewer colorings, and so have fewer
+//! classes.
+
+use crate::board::{bit, positions, Board};
+use crate::rules::Rules;
+
+/// Colorings of the board as `(a, b)`, the color of (x, y) being
+/// `(a * x + b * y) % 3`.
+const COLORINGS: [(usize, usize); 4] = [(1, 1), (1, 2), (1, 0), (0, 1)];
+
+/// The class signature of a board. Boards in different classes can never
+/// be reached from one another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PositionClass(u8);
+
+impl Board {
+    pub fn position_class(&self) -> PositionClass {
+        let mut class = 0;
+        for (i, (a, b)) in colorings(self.rules()).enumerate() {
+            let mut colors = [0; 3];
+            for p in positions(self.pieces()) {
+                colors[(a * p.x + b * p.y) % 3] += 1;
+            }
+            let parities = ((colors[0] ^ colors[1]) & 1) | (((colors[1] ^ colors[2]) & 1) << 1);
+            class |= parities << (2 * i);
+        }
+        PositionClass(class)
+    }
+
+    /// Classes of all the boards with a single piece on this board's shape.
+    pub fn single_piece_classes(&self) -> Vec<PositionClass> {
+        let mut classes = vec![];
+        for p in positions(self.holes()) {
+            let class = self.with_pieces(bit(p)).position_class();
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        classes
+    }
+}
+
+/// Colorings where every jump of the rules covers the three colors.
+fn colorings(rules: Rules) -> impl Iterator<Item = (usize, usize)> {
+    COLORINGS.into_iter().filter(move |(a, b)| {
+        rules
+            .directions()
+            .iter()
+            .all(|(dx, dy)| (*a as i32 * dx + *b as i32 * dy).rem_euclid(3) != 0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jumps_keep_the_class() {
+        let mut board = Board::new();
+        let class = board.position_class();
+        while let Some(m) = board.find_moves().last() {
+            board = board.move_piece(m.from, m.to).expect("valid move");
+            assert_eq!(class, board.position_class());
+        }
+    }
+
+    #[test]
+    fn english_board_has_all_single_piece_classes() {
+        // a single piece is on one of the 3 colors of each coloring
+        assert_eq!(9, Board::new().single_piece_classes().len());
+    }
+
+    #[test]
+    fn three_in_a_row_is_not_a_single_piece_class() {
+        let board = Board::deserialize(
+            "
+  ---  
+  ---  
+-------
+--***--
+-------
+  ---  
+  ---  
+",
+        );
+        assert!(!board
+            .single_piece_classes()
+            .contains(&board.position_class()));
+    }
+
+    #[test]
+    fn triangle_has_fewer_classes() {
+        // only one of the colorings covers the three colors with the
+        // triangle's diagonal jumps
+        let board = Board::triangle();
+        assert_eq!(3, board.single_piece_classes().len());
+        let mut board = board;
+        let class = board.position_class();
+        while let Some(m) = board.find_moves().last() {
+            board = board.move_piece(m.from, m.to).expect("valid move");
+            assert_eq!(class, board.position_class());
+        }
+    }
+}
@@ -0,0 +1,505 @@
+//! Game records
+//!
+//! A text format to save games and solutions, in the spirit of PGN. The
+//! record starts with tag pairs, one per line, followed by the numbered
+//! moves:
+//!
+//! ```text
+//! [Board "english"]
+//! [Rules "orthogonal"]
+//! [Start "  ***  /  ***  /*******/***-***/*******/  ***  /  ***  "]
+//! [Player "Ada"]
+//!
+//! 1. d2-d4 2. f3-d3
+//! ```
+//!
+//! Boards are written as their serialized rows joined by `/`. The `Board`
+//! tag names one of the standard boards, whose shape the start and goal
+//! boards must have, or is `custom`. The optional `Goal` tag is the exact
+//! board the game must end in, and `Notation` tells how the moves are
+//! written when it is not in coordinates. Any other tag, like the player,
+//! the date or the solver used, is kept as is.
+//!
+//! Every move is checked to be valid when a record is read or its moves
+//! are set.
+
+use crate::board::Board;
+use crate::error::{Error, RecordError};
+use crate::game::Game;
+use crate::goal::Goal;
+use crate::move_struct::Move;
+use crate::notation::Notation;
+use crate::rules::Rules;
+use std::fmt;
+use std::str::FromStr;
+
+/// Moves written on each line.
+const MOVES_PER_LINE: usize = 8;
+
+/// Tags written from the boards and the notation, that can not be set.
+const RESERVED_TAGS: [&str; 5] = ["Board", "Rules", "Start", "Goal", "Notation"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    start: Board,
+    /// board with the shape of the start the game must end in, any single
+    /// piece if there is none
+    goal: Option<Board>,
+    /// tags other than the reserved ones, in the order they are written
+    tags: Vec<(String, String)>,
+    /// valid moves from the start
+    moves: Vec<Move>,
+}
+
+impl Record {
+    pub fn new(start: Board) -> Record {
+        Record {
+            start,
+            goal: None,
+            tags: vec![],
+            moves: vec![],
+        }
+    }
+
+    /// Record of the moves played in the game, up to its cursor.
+    pub fn from_game(game: &Game) -> Record {
+        Record {
+            moves: game.played().to_vec(),
+            ..Record::new(game.start().clone())
+        }
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Replaces the moves of the record, if they can all be played from
+    /// the start.
+    pub fn set_moves(&mut self, moves: Vec<Move>) -> Result<(), Error<'static>> {
+        self.start.replay(&moves)?;
+        self.moves = moves;
+        Ok(())
+    }
+
+    /// The game of the record, with all its moves played.
+    pub fn game(&self) -> Game {
+        Game::from_moves(self.start.clone(), &self.moves).expect("moves of a record are valid")
+    }
+
+    pub fn goal(&self) -> Goal {
+        match &self.goal {
+            Some(board) => Goal::Board(board.clone()),
+            None => Goal::OnePiece,
+        }
+    }
+
+    pub fn goal_board(&self) -> Option<&Board> {
+        self.goal.as_ref()
+    }
+
+    /// Sets the board the game must end in, if it has the shape and the
+    /// rules of the start.
+    pub fn set_goal(&mut self, goal: Option<Board>) -> Result<(), RecordError> {
+        if let Some(board) = &goal {
+            if !same_shape(board, &self.start) {
+                return Err(RecordError::InvalidTag(
+                    "Goal".to_string(),
+                    board_value(board),
+                ));
+            }
+        }
+        self.goal = goal;
+        Ok(())
+    }
+
+    /// Tags other than the reserved ones, in the order they are written.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Value of a tag other than the reserved ones.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, adding it after the others if it is new.
+    /// The name must not be empty, be one of the reserved tags or hold
+    /// blanks, `]` or `"`.
+    pub fn set_tag(&mut self, name: &str, value: &str) -> Result<(), RecordError> {
+        let invalid = |c: char| c.is_whitespace() || c == ']' || c == '"';
+        if name.is_empty() || name.contains(invalid) || RESERVED_TAGS.contains(&name) {
+            return Err(RecordError::InvalidTagName(name.to_string()));
+        }
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Writes the record with the moves in the notation.
+    pub fn write(&self, notation: Notation) -> String {
+        let mut text = String::new();
+        let mut tag = |name: &str, value: &str| {
+            text.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+        };
+        let name = Board::standard()
+            .into_iter()
+            .find(|(_, shape)| {
+                shape.size() == self.start.size() && shape.holes() == self.start.holes()
+            })
+            .map_or("custom", |(name, _)| name);
+        tag("Board", name);
+        tag("Rules", self.start.rules().name());
+        tag("Start", &board_value(&self.start));
+        if let Some(goal) = &self.goal {
+            tag("Goal", &board_value(goal));
+        }
+        if notation != Notation::Coordinates {
+            tag("Notation", notation_name(notation));
+        }
+        for (name, value) in &self.tags {
+            tag(name, value);
+        }
+
+        let mut board = self.start.clone();
+        for (i, m) in self.moves.iter().enumerate() {
+            text.push(match i % MOVES_PER_LINE {
+                0 => '\n',
+                _ => ' ',
+            });
+            let written = board
+                .format_move(*m, notation)
+                .expect("moves of a record are on the board");
+            text.push_str(&format!("{}. {}", i + 1, written));
+            board = board
+                .move_piece(m.from, m.to)
+                .expect("moves of a record are valid");
+        }
+        text.push('\n');
+        text
+    }
+}
+
+/// Written with the moves in coordinates.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.write(Notation::Coordinates))
+    }
+}
+
+impl FromStr for Record {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<Record, RecordError> {
+        let mut lines = text.lines().zip(1..).peekable();
+        let mut tags = vec![];
+        while let Some((line, number)) = lines.peek() {
+            let line = line.trim();
+            if !line.starts_with('[') {
+                break;
+            }
+            tags.push(parse_tag(line).ok_or(RecordError::Malformed(*number))?);
+            lines.next();
+        }
+        let movetext: Vec<&str> = lines.map(|(line, _)| line).collect();
+
+        let mut take = |name: &'static str| -> Option<String> {
+            let index = tags.iter().position(|(n, _)| n == name)?;
+            Some(tags.remove(index).1)
+        };
+        let board_name = take("Board").ok_or(RecordError::MissingTag("Board"))?;
+        // without the tag, the rules of the shape are kept
+        let rules = match take("Rules") {
+            Some(value) => Some(
+                Rules::from_name(&value)
+                    .ok_or(RecordError::InvalidTag("Rules".to_string(), value.clone()))?,
+            ),
+            None => None,
+        };
+        let shape = match board_name.as_str() {
+            "custom" => None,
+            name => Some(Board::named(name).ok_or(RecordError::InvalidTag(
+                "Board".to_string(),
+                board_name.clone(),
+            ))?),
+        };
+        let read_board = |name: &str, value: &str| {
+            let rows = value.replace('/', "\n");
+            let board = match &shape {
+                Some(shape) => Board::parse_with_shape(&rows, shape),
+                None => Board::parse_any_shape(&rows),
+            };
+            board
+                .map(|board| board.with_rules(rules.unwrap_or(board.rules())))
+                .map_err(|err| RecordError::InvalidBoard(name.to_string(), err))
+        };
+        let start = read_board(
+            "Start",
+            &take("Start").ok_or(RecordError::MissingTag("Start"))?,
+        )?;
+        let goal = match take("Goal") {
+            Some(value) => {
+                let goal = read_board("Goal", &value)?;
+                if !same_shape(&goal, &start) {
+                    return Err(RecordError::InvalidTag("Goal".to_string(), value));
+                }
+                Some(goal)
+            }
+            None => None,
+        };
+        let notation = match take("Notation") {
+            None => Notation::Coordinates,
+            Some(value) => parse_notation(&value)
+                .ok_or(RecordError::InvalidTag("Notation".to_string(), value))?,
+        };
+
+        let mut board = start.clone();
+        let mut moves = vec![];
+        let tokens = movetext.iter().flat_map(|line| line.split_whitespace());
+        for token in tokens.filter(|token| !is_move_number(token)) {
+            let m = board
+                .parse_move(token, notation)
+                .map_err(RecordError::InvalidNotation)?;
+            board = board.move_piece(m.from, m.to).map_err(|err| {
+                RecordError::InvalidMove(Box::new(Error::InvalidMoveAt(moves.len(), Box::new(err))))
+            })?;
+            moves.push(m);
+        }
+
+        Ok(Record {
+            start,
+            goal,
+            tags,
+            moves,
+        })
+    }
+}
+
+fn same_shape(board: &Board, other: &Board) -> bool {
+    board.size() == other.size() && board.holes() == other.holes() && board.rules() == other.rules()
+}
+
+fn board_value(board: &Board) -> String {
+    let serialized = board.serialize();
+    serialized
+        .trim_start_matches('\n')
+        .lines()
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn is_move_number(token: &str) -> bool {
+    token
+        .strip_suffix('.')
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Reads `[Name "value"]`.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), unescape(value)?))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(match chars.next()? {
+                'n' => '\n',
+                c => c,
+            }),
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+fn notation_name(notation: Notation) -> &'static str {
+    match notation {
+        Notation::Coordinates => "coordinates",
+        Notation::Numbered => "numbered",
+        Notation::Grid => "grid",
+    }
+}
+
+fn parse_notation(name: &str) -> Option<Notation> {
+    [Notation::Coordinates, Notation::Numbered, Notation::Grid]
+        .into_iter()
+        .find(|notation| notation_name(*notation) == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn write_and_read_a_solution() {
+        let mut solver = Solver::new();
+        let mut record = Record::new(Board::new());
+        record
+            .set_moves(solver.solve().expect("has a solution"))
+            .expect("valid moves");
+        for (name, value) in [
+            ("Player", "Ada \"the\" solver"),
+            ("Solver", "depth first search"),
+            ("Comment", "first line\nsecond line \\n"),
+        ] {
+            record.set_tag(name, value).expect("valid tag");
+        }
+
+        for notation in [Notation::Coordinates, Notation::Numbered, Notation::Grid] {
+            let text = record.write(notation);
+            let read: Record = text.parse().expect("valid record");
+            assert_eq!(record, read, "{}", text);
+        }
+        let text = record.to_string();
+        assert!(text.starts_with(
+            "[Board \"english\"]
+[Rules \"orthogonal\"]
+[Start \"  ***  /  ***  /*******/***-***/*******/  ***  /  ***  \"]
+[Player \"Ada \\\"the\\\" solver\"]
+[Solver \"depth first search\"]
+[Comment \"first line\\nsecond line \\\\n\"]
+
+1. "
+        ));
+        assert_eq!(Some("depth first search"), record.tag("Solver"));
+        assert_eq!(1, record.game().board().count_pieces());
+        assert_eq!(Some("first line\nsecond line \\n"), record.tag("Comment"));
+
+        let moves = record.moves().to_vec();
+        assert!(matches!(
+            record.set_moves(vec![moves[1]]),
+            Err(Error::InvalidMoveAt(0, _))
+        ));
+        assert_eq!(moves, record.moves());
+    }
+
+    #[test]
+    fn goal_and_rules_are_kept() {
+        let start = Board::french().with_rules(Rules::Diagonal);
+        let mut game = Game::new(start.clone());
+        let m = start.find_moves()[0];
+        game.play(m).expect("valid move");
+        let mut record = Record::from_game(&game);
+        record
+            .set_goal(Some(game.board().clone()))
+            .expect("same shape");
+
+        let read: Record = record.to_string().parse().expect("valid record");
+        assert_eq!(Rules::Diagonal, read.start().rules());
+        assert!(read.goal().reached(read.game().board()));
+        assert_eq!(record, read);
+
+        assert!(record.set_goal(Some(Board::new())).is_err());
+        assert!(record
+            .set_goal(Some(game.board().with_rules(Rules::Orthogonal)))
+            .is_err());
+        assert_eq!(Some(game.board()), record.goal_board());
+        record.set_goal(None).expect("no goal");
+        assert_eq!(None, record.goal_board());
+
+        // without a rules tag, the rules of the shape
+        let triangle = Record::new(Board::triangle()).to_string();
+        let without_rules: String = triangle
+            .lines()
+            .filter(|line| !line.starts_with("[Rules"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let read: Record = without_rules.parse().expect("valid record");
+        assert_eq!(Rules::Triangular, read.start().rules());
+        assert_eq!(&Board::triangle(), read.start());
+    }
+
+    #[test]
+    fn tag_names() {
+        let mut record = Record::new(Board::new());
+        for name in [
+            "Event Name",
+            "Event]",
+            "Ev\"ent",
+            "",
+            "Start",
+            "Goal",
+            "Rules",
+        ] {
+            assert_eq!(
+                Err(RecordError::InvalidTagName(name.to_string())),
+                record.set_tag(name, "x")
+            );
+        }
+        record.set_tag("Event", "first").expect("valid tag");
+        record.set_tag("Event", "second").expect("valid tag");
+        assert_eq!(
+            &[("Event".to_string(), "second".to_string())],
+            record.tags()
+        );
+        assert_eq!(record, record.to_string().parse().expect("valid record"));
+    }
+
+    #[test]
+    fn invalid_records() {
+        let record = |moves: &str| {
+            format!(
+                "[Board \"english\"]\n[Start \"  ***/  ***/*******/***-***/*******/  ***/  ***\"]\n\n{}",
+                moves
+            )
+        };
+        assert_eq!(
+            1,
+            record("1. d2-d4")
+                .parse::<Record>()
+                .expect("valid")
+                .moves()
+                .len()
+        );
+
+        match record("1. d2-d4 2. d2-d4").parse::<Record>() {
+            Err(RecordError::InvalidMove(err)) => {
+                assert!(matches!(*err, Error::InvalidMoveAt(1, _)))
+            }
+            other => panic!("expected the second move to fail, got {:?}", other),
+        }
+        assert!(matches!(
+            record("1. a1-a3").parse::<Record>(),
+            Err(RecordError::InvalidNotation(_))
+        ));
+        assert_eq!(
+            Err(RecordError::MissingTag("Start")),
+            "[Board \"english\"]\n".parse::<Record>()
+        );
+        assert_eq!(
+            Err(RecordError::Malformed(2)),
+            "[Board \"english\"]\n[Start]\n".parse::<Record>()
+        );
+        assert!(matches!(
+            "[Board \"french\"]\n[Start \"  ***/  ***/*******/***-***/*******/  ***/  ***\"]"
+                .parse::<Record>(),
+            Err(RecordError::InvalidBoard(..))
+        ));
+        assert!(matches!(
+            "[Board \"custom\"]\n[Start \"***/*-*/***\"]\n[Goal \"-*-/---/--\"]".parse::<Record>(),
+            Err(RecordError::InvalidTag(..))
+        ));
+    }
+}
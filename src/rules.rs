@@ -0,0 +1,72 @@
+//! Jump rules
+//!
+//! The directions a piece can jump in. The square boards jump along rows
+//! and columns. The triangular board is stored with row `y` holding the
+//! holes `0..=y`, so besides rows and columns its pieces also jump along
+//! the diagonal where x and y grow together. Some variants of the square
+//! boards allow jumps along both diagonals too.
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rules {
+    /// along rows and columns
+    #[default]
+    Orthogonal,
+    /// the six directions of a triangular grid
+    Triangular,
+    /// along rows, columns and both diagonals
+    Diagonal,
+}
+
+impl Rules {
+    /// Steps of one space in each direction a piece can jump in, in the
+    /// order moves are generated.
+    pub fn directions(&self) -> &'static [(i32, i32)] {
+        match self {
+            Rules::Orthogonal => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Rules::Triangular => &[(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, 1)],
+            Rules::Diagonal => &[
+                (-1, 0),
+                (1, 0),
+                (0, -1),
+                (0, 1),
+                (-1, -1),
+                (1, 1),
+                (1, -1),
+                (-1, 1),
+            ],
+        }
+    }
+
+    /// Name of the rules in files, in lowercase.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Rules::Orthogonal => "orthogonal",
+            Rules::Triangular => "triangular",
+            Rules::Diagonal => "diagonal",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Rules> {
+        [Rules::Orthogonal, Rules::Triangular, Rules::Diagonal]
+            .into_iter()
+            .find(|rules| rules.name() == name)
+    }
+
+    /// The step of a jump from `from` with difference `diff` between end
+    /// and start positions, if it is along one of the directions. The
+    /// number of steps is returned too.
+    pub(crate) fn step_of(&self, diff: (i32, i32)) -> Option<((i32, i32), i32)> {
+        self.directions().iter().find_map(|step| {
+            let steps = if step.0 != 0 {
+                diff.0 / step.0
+            } else {
+                diff.1 / step.1
+            };
+            if steps > 0 && (step.0 * steps, step.1 * steps) == diff {
+                Some((*step, steps))
+            } else {
+                None
+            }
+        })
+    }
+}
@@ -0,0 +1,340 @@
+//! All the solutions of a board
+//!
+//! Counting the solutions reuses the counts of boards reached through
+//! different move orders, so it only visits each reachable board once. The
+//! counts are kept for the canonical form of the boards that have
+//! solutions, the boards without any are left to the dead positions of the
+//! solver.
+//!
+//! Solutions that are a rotation or a mirror of each other can be counted
+//! once, using Burnside's lemma: the number of distinct solutions is the
+//! average, over the symmetries of the game, of the number of solutions
+//! that symmetry leaves unchanged. A solution is unchanged by a symmetry
+//! only if every one of its moves is.
+
+use crate::board::{Bits, Board};
+use crate::move_struct::Move;
+use crate::solver::Solver;
+use crate::symmetry::{Canonicalizer, Transform};
+use std::collections::HashMap;
+
+impl Solver {
+    /// Number of different move sequences that reach the goal.
+    pub fn count_solutions(&mut self) -> u128 {
+        self.count_fixed_by(Transform::Identity)
+    }
+
+    /// Number of solutions, counting the ones that are a rotation or a
+    /// mirror of each other only once.
+    pub fn count_solutions_modulo_symmetry(&mut self) -> u128 {
+        let symmetries = self.game_symmetries();
+        let fixed: u128 = symmetries.iter().map(|t| self.count_fixed_by(*t)).sum();
+        fixed / symmetries.len() as u128
+    }
+
+    /// Lazily goes through all the solutions, in the same order they are
+    /// found by the search.
+    pub fn solutions(&mut self) -> Solutions<'_> {
+        let board = self.board().clone();
+        let start_solved = self.goal().reached(&board);
+        let stack = if self.class_can_reach_goal() && !start_solved {
+            vec![Frame::new(board)]
+        } else {
+            vec![]
+        };
+        Solutions {
+            solver: self,
+            stack,
+            path: vec![],
+            start_solved,
+        }
+    }
+
+    /// Symmetries of the board that also keep the goal the same.
+    fn game_symmetries(&self) -> Vec<Transform> {
+        let board = self.board();
+        board
+            .symmetries()
+            .into_iter()
+            .filter(|t| board.transform(*t) == *board && self.goal().symmetric_under(*t))
+            .collect()
+    }
+
+    fn count_fixed_by(&mut self, t: Transform) -> u128 {
+        if !self.class_can_reach_goal() {
+            return 0;
+        }
+        // all the moves are counted with the identity, so a board has as
+        // many solutions as its rotations and mirrors that keep the game
+        // the same
+        let symmetries = match t {
+            Transform::Identity => self.game_symmetries(),
+            _ => vec![Transform::Identity],
+        };
+        let canonicalizer = Canonicalizer::new(self.board(), &symmetries);
+        let mut counts = HashMap::new();
+        self.count_board(self.board().clone(), t, &canonicalizer, &mut counts)
+    }
+
+    /// Number of solutions from `board` with only moves left unchanged by
+    /// `t`. The counts are kept by canonical pieces, except for the boards
+    /// without solution which the solver already remembers.
+    fn count_board(
+        &mut self,
+        board: Board,
+        t: Transform,
+        canonicalizer: &Canonicalizer,
+        counts: &mut HashMap<Bits, u128>,
+    ) -> u128 {
+        if self.goal().reached(&board) {
+            return 1;
+        }
+        let code = canonicalizer.canonical(board.pieces());
+        if let Some(count) = counts.get(&code) {
+            return *count;
+        }
+        if self.known_dead(&board) {
+            return 0;
+        }
+        let mut count = 0;
+        for m in board.find_moves() {
+            if t.apply_move(m, board.size()) != m {
+                continue;
+            }
+            let new_board = board
+                .move_piece(m.from, m.to)
+                .expect("all moves should be valid at this point");
+            count += self.count_board(new_board, t, canonicalizer, counts);
+        }
+        if count == 0 && t == Transform::Identity {
+            self.mark_dead(&board);
+        } else {
+            counts.insert(code, count);
+        }
+        count
+    }
+}
+
+/// Iterator over the solutions of a board, see `Solver::solutions`.
+pub struct Solutions<'a> {
+    solver: &'a mut Solver,
+    /// boards on the current line of moves
+    stack: Vec<Frame>,
+    /// moves from the start to the last board on the stack
+    path: Vec<Move>,
+    /// the start board reaches the goal without any move
+    start_solved: bool,
+}
+
+struct Frame {
+    board: Board,
+    moves: Vec<Move>,
+    next: usize,
+    /// a solution was found going through this board
+    solved: bool,
+}
+
+impl Frame {
+    fn new(board: Board) -> Frame {
+        Frame {
+            moves: board.find_moves(),
+            board,
+            next: 0,
+            solved: false,
+        }
+    }
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Vec<Move>;
+
+    fn next(&mut self) -> Option<Vec<Move>> {
+        if self.start_solved {
+            self.start_solved = false;
+            return Some(vec![]);
+        }
+        loop {
+            let frame = self.stack.last_mut()?;
+            if frame.next == frame.moves.len() {
+                let frame = self.stack.pop().expect("stack is not empty");
+                if !frame.solved {
+                    self.solver.mark_dead(&frame.board);
+                }
+                self.path.pop();
+                continue;
+            }
+
+            let m = frame.moves[frame.next];
+            frame.next += 1;
+            let board = frame
+                .board
+                .move_piece(m.from, m.to)
+                .expect("all moves should be valid at this point");
+            if self.solver.goal().reached(&board) {
+                for frame in self.stack.iter_mut() {
+                    frame.solved = true;
+                }
+                let mut solution = self.path.clone();
+                solution.push(m);
+                return Some(solution);
+            }
+            if !self.solver.known_dead(&board) {
+                self.path.push(m);
+                self.stack.push(Frame::new(board));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::bit;
+    use crate::position::Position;
+    use crate::rules::Rules;
+
+    fn count_distinct_modulo(
+        solutions: &[Vec<Move>],
+        symmetries: &[Transform],
+        size: usize,
+    ) -> usize {
+        let mut distinct: Vec<Vec<Move>> = solutions
+            .iter()
+            .map(|s| {
+                symmetries
+                    .iter()
+                    .map(|t| {
+                        s.iter()
+                            .map(|m| t.apply_move(*m, size))
+                            .collect::<Vec<Move>>()
+                    })
+                    .min()
+                    .expect("identity is a symmetry")
+            })
+            .collect();
+        distinct.sort();
+        distinct.dedup();
+        distinct.len()
+    }
+
+    fn symmetric_board() -> Board {
+        Board::deserialize(
+            "
+  ---  
+  -*-  
+--***--
+-**-**-
+--***--
+  -*-  
+  ---  
+",
+        )
+    }
+
+    #[test]
+    fn count_matches_iterator() {
+        let mut solver = Solver::from_board(symmetric_board());
+        let count = solver.count_solutions();
+        let solutions: Vec<Vec<Move>> = Solver::from_board(symmetric_board()).solutions().collect();
+        assert!(count > 1);
+        assert_eq!(count, solutions.len() as u128);
+
+        let mut distinct = solutions.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), solutions.len());
+    }
+
+    #[test]
+    fn iterator_gives_valid_solutions() {
+        let board = symmetric_board();
+        for solution in Solver::from_board(board.clone()).solutions() {
+            let mut replay = board.clone();
+            for m in solution {
+                replay = replay.move_piece(m.from, m.to).expect("valid move");
+            }
+            assert_eq!(1, replay.count_pieces());
+        }
+    }
+
+    #[test]
+    fn count_modulo_symmetry() {
+        let board = symmetric_board();
+        let mut solver = Solver::from_board(board.clone());
+        let solutions: Vec<Vec<Move>> = solver.solutions().collect();
+        let symmetries = solver.game_symmetries();
+        assert_eq!(8, symmetries.len());
+
+        let expected = count_distinct_modulo(&solutions, &symmetries, 7);
+        assert_eq!(expected as u128, solver.count_solutions_modulo_symmetry());
+        assert!((expected as u128) < solver.count_solutions());
+    }
+
+    #[test]
+    fn triangle_counts() {
+        // the published counts for each of the four different empty holes
+        // of the triangle, numbered 1 at the top and row by row
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        for (x, y, expected) in [(0, 0, 29760), (0, 1, 14880), (0, 2, 85258), (1, 2, 1550)] {
+            let board = full.with_pieces(full.pieces() & !bit(Position { x, y }));
+            let mut solver = Solver::from_board(board.clone());
+            assert_eq!(expected, solver.count_solutions(), "{}", board);
+            let solutions = Solver::from_board(board.clone()).solutions().count();
+            assert_eq!(expected, solutions as u128, "{}", board);
+        }
+    }
+
+    #[test]
+    fn triangle_count_modulo_symmetry() {
+        let mut solver = Solver::from_board(Board::triangle());
+        let solutions: Vec<Vec<Move>> = solver.solutions().collect();
+        let symmetries = solver.game_symmetries();
+        assert_eq!(
+            vec![Transform::Identity, Transform::FlipThroughTop],
+            symmetries
+        );
+
+        let expected = count_distinct_modulo(&solutions, &symmetries, 5);
+        assert_eq!(expected as u128, solver.count_solutions_modulo_symmetry());
+        assert!((expected as u128) < solver.count_solutions());
+    }
+
+    #[test]
+    fn no_solutions() {
+        let board = Board::deserialize(
+            "
+  ---  
+  ---  
+-------
+--***--
+-------
+  ---  
+  ---  
+",
+        );
+        let mut solver = Solver::from_board(board);
+        assert_eq!(0, solver.count_solutions());
+        assert_eq!(None, solver.solutions().next());
+    }
+
+    #[test]
+    fn diagonals_add_solutions() {
+        let board = Board::deserialize(
+            "
+  ---  
+  ---  
+--**---
+--**---
+-------
+  ---  
+  ---  
+",
+        );
+        let orthogonal = Solver::from_board(board.clone()).count_solutions();
+        let mut solver = Solver::from_board(board.with_rules(Rules::Diagonal));
+        let diagonal = solver.count_solutions();
+        assert_eq!(12, orthogonal);
+        assert!(diagonal > orthogonal);
+        assert_eq!(diagonal, solver.solutions().count() as u128);
+    }
+}
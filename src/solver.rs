@@ -1,45 +1,303 @@
 //! Brute force solver
+//!
+//! The solver does a depth first search over the move graph. Positions
+//! that were already fully searched without reaching a solution are kept
+//! in a transposition table, so the same board reached through a
+//! different move order is not searched again. Pagoda functions can be
+//! given to prune boards that can never reach the goal.
+//!
+//! Before searching, the solver checks that the board is in the same
+//! position class as the boards that reach the goal, so boards that can
+//! never be solved are rejected without a search.
+//!
+//! A `Database` of winnable boards can be given to skip every board that
+//! can no longer be reduced to a single piece.
+//!
+//! With more than one thread, the search is split between them as
+//! described in `parallel`. The search can also be limited in time and
+//! number of boards, or cancelled from another thread, see `limits`.
 
-use crate::board::Board;
+use crate::board::{Bits, Board};
+use crate::database::Database;
+use crate::error::{Error, ParseError};
+use crate::goal::Goal;
+use crate::limits::{Budget, Limit, Limits, Stats};
 use crate::move_struct::Move;
+use crate::pagoda::Pagoda;
+use crate::parallel;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Result of a search.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Solved(Vec<Move>),
+    /// there is no solution, and how that was found out
+    Impossible(Proof),
+    /// a limit was reached before the search could tell
+    GaveUp(Limit, Stats),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Proof {
+    /// the board is not in the position class of any board reaching the goal
+    PositionClass,
+    /// every move was searched
+    Search,
+}
 
 pub struct Solver {
     board: Board,
-    count: i32,
+    goal: Goal,
+    /// boards searched
+    count: u64,
+    /// hash codes of positions already proven to have no solution
+    dead_positions: HashSet<Bits>,
+    cache_hits: u64,
+    pagodas: Vec<Pagoda>,
+    /// for each pagoda, the smallest value a board reaching the goal can
+    /// have, if it is known
+    pagoda_targets: Vec<Option<i32>>,
+    /// boards discarded by a pagoda or the database
+    pruned: u64,
+    database: Option<Arc<Database>>,
+    threads: usize,
+    limits: Limits,
+    /// the limit reached by the running search
+    gave_up: Option<Limit>,
 }
 
 impl Solver {
     pub fn new() -> Solver {
+        Solver::from_board(Board::new())
+    }
+    pub fn from_board(board: Board) -> Solver {
         Solver {
-            board: Board::new(),
+            board,
+            goal: Goal::OnePiece,
             count: 0,
+            dead_positions: HashSet::new(),
+            cache_hits: 0,
+            pagodas: vec![],
+            pagoda_targets: vec![],
+            pruned: 0,
+            database: None,
+            threads: 1,
+            limits: Limits::default(),
+            gave_up: None,
         }
     }
-    pub fn from_str(b_str: &str) -> Solver {
-        Solver {
-            board: Board::deserialize(b_str),
-            count: 0,
+
+    /// Search for `goal` instead of a single piece left anywhere.
+    pub fn with_goal(mut self, goal: Goal) -> Solver {
+        self.goal = goal;
+        self.update_pagoda_targets();
+        self
+    }
+
+    /// Prune boards with a pagoda value smaller than the one of any
+    /// board reaching the goal. Fails if one of them is not a pagoda for
+    /// the board.
+    pub fn with_pagodas(mut self, pagodas: Vec<Pagoda>) -> Result<Solver, Error<'static>> {
+        for pagoda in pagodas.iter() {
+            pagoda.validate(&self.board)?;
         }
+        self.pagodas.extend(pagodas);
+        self.update_pagoda_targets();
+        Ok(self)
+    }
+
+    /// Skip the boards the database knows can not be won. The database is
+    /// only used if the board of the solver is one of its winnable boards,
+    /// as it knows nothing about the boards it can not reach.
+    pub fn with_database(mut self, database: Arc<Database>) -> Solver {
+        if database.winnable(&self.board) {
+            self.database = Some(database);
+        }
+        self
+    }
+
+    /// Search with `threads` threads, one by default. With more threads
+    /// the solution found may not be the one found with a single thread.
+    pub fn with_threads(mut self, threads: usize) -> Solver {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Give up a search after it ran for `time`.
+    pub fn with_time_limit(mut self, time: Duration) -> Solver {
+        self.limits.time = Some(time);
+        self
+    }
+
+    /// Give up a search after it went through `nodes` boards. With more
+    /// than one thread a few more boards may be searched.
+    pub fn with_node_limit(mut self, nodes: u64) -> Solver {
+        self.limits.nodes = Some(nodes);
+        self
     }
+
+    /// Give up a search once `cancel` is set, by this thread or another.
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Solver {
+        self.limits.cancel = Some(cancel);
+        self
+    }
+
+    /// The positions known to have no solution depend on the goal and the
+    /// pagodas, so they are forgotten when those change.
+    fn update_pagoda_targets(&mut self) {
+        self.pagoda_targets = self
+            .pagodas
+            .iter()
+            .map(|pagoda| self.goal.pagoda_target(pagoda, &self.board))
+            .collect();
+        self.clear_cache();
+    }
+
+    /// A solution, or `None` if there is none or the search gave up.
     pub fn solve(&mut self) -> Option<Vec<Move>> {
-        let mut solution = self.solve_board(self.board.clone())?;
-        solution.reverse();
-        Some(solution)
-    }
-    fn solve_board(&mut self, board: Board) -> Option<Vec<Move>> {
-        //println!("Enter board: {}", board.serialize());
-        let moves = board.find_moves();
-        if moves.len() == 0 && board.find_pieces().len() == 1 {
-            return Some(Vec::with_capacity(32));
+        match self.search() {
+            Outcome::Solved(solution) => Some(solution),
+            Outcome::Impossible(_) | Outcome::GaveUp(..) => None,
+        }
+    }
+
+    /// Like `solve`, but tells why there is no solution.
+    pub fn search(&mut self) -> Outcome {
+        if !self.class_can_reach_goal() {
+            return Outcome::Impossible(Proof::PositionClass);
+        }
+        let before = (self.count, self.cache_hits, self.pruned);
+        let budget = self.limits.start(self.count);
+        self.gave_up = None;
+        let solution = if self.threads > 1 {
+            self.solve_parallel(&budget)
+        } else {
+            self.solve_board(self.board.clone(), &budget)
+                .map(|mut solution| {
+                    solution.reverse();
+                    solution
+                })
+        };
+        match (solution, self.gave_up.take()) {
+            (Some(solution), _) => Outcome::Solved(solution),
+            (None, Some(limit)) => Outcome::GaveUp(
+                limit,
+                Stats {
+                    nodes: self.count - before.0,
+                    cache_hits: self.cache_hits - before.1,
+                    pruned: self.pruned - before.2,
+                    elapsed: budget.started.elapsed(),
+                },
+            ),
+            (None, None) => Outcome::Impossible(Proof::Search),
+        }
+    }
+
+    fn solve_parallel(&mut self, budget: &Budget) -> Option<Vec<Move>> {
+        let (solution, counts, gave_up) = parallel::search(self, self.threads, budget);
+        self.count += counts.nodes;
+        self.cache_hits += counts.cache_hits;
+        self.pruned += counts.pruned;
+        self.gave_up = gave_up;
+        solution
+    }
+
+    /// Number of times a position was skipped because it was already
+    /// known to have no solution.
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits
+    }
+
+    /// Forgets all the positions known to have no solution.
+    pub fn clear_cache(&mut self) {
+        self.dead_positions.clear();
+        self.cache_hits = 0;
+    }
+
+    pub(crate) fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub(crate) fn goal(&self) -> &Goal {
+        &self.goal
+    }
+
+    /// False if the position class of the board shows it can never reach
+    /// the goal.
+    pub(crate) fn class_can_reach_goal(&self) -> bool {
+        match self.goal.classes(&self.board) {
+            Some(classes) => classes.contains(&self.board.position_class()),
+            None => true,
+        }
+    }
+
+    /// True if `board` is known to never reach the goal, without searching
+    /// its moves. Counts the board as searched otherwise.
+    pub(crate) fn known_dead(&mut self, board: &Board) -> bool {
+        if board.count_pieces() <= self.goal.min_pieces() {
+            return true;
+        }
+        let code = board.hash_code();
+        if self.dead_positions.contains(&code) {
+            self.cache_hits += 1;
+            return true;
         }
         self.count += 1;
-        //println!("moves found:\n {:?}", moves);
+        if self.can_prune(board) {
+            self.pruned += 1;
+            self.mark_dead(board);
+            return true;
+        }
+        false
+    }
+
+    /// True if the database or one of the pagodas show that `board` can
+    /// never reach the goal.
+    pub(crate) fn can_prune(&self, board: &Board) -> bool {
+        if let (Goal::OnePiece, Some(database)) = (&self.goal, &self.database) {
+            if !database.winnable(board) {
+                return true;
+            }
+        }
+        let below_target = |(pagoda, target): (&Pagoda, &Option<i32>)| match target {
+            Some(target) => pagoda.value(board) < *target,
+            None => false,
+        };
+        self.pagodas
+            .iter()
+            .zip(self.pagoda_targets.iter())
+            .any(below_target)
+    }
+
+    /// Remember that `board` can never reach the goal.
+    pub(crate) fn mark_dead(&mut self, board: &Board) {
+        self.dead_positions.insert(board.hash_code());
+    }
+
+    fn solve_board(&mut self, board: Board, budget: &Budget) -> Option<Vec<Move>> {
+        if self.gave_up.is_some() {
+            return None;
+        }
+        if self.goal.reached(&board) {
+            return Some(Vec::with_capacity(32));
+        }
+        if self.known_dead(&board) {
+            return None;
+        }
+        if let Some(limit) = budget.exceeded(self.count) {
+            self.gave_up = Some(limit);
+            return None;
+        }
 
-        for m in moves {
+        for m in board.find_moves() {
             let new_board = board
                 .move_piece(m.from, m.to)
                 .expect("all moves should be valid at this point");
-            let option_moves = self.solve_board(new_board);
+            let option_moves = self.solve_board(new_board, budget);
             match option_moves {
                 Some(mut winning_moves) => {
                     winning_moves.push(m);
@@ -48,10 +306,29 @@ impl Solver {
                 None => continue,
             }
         }
+        // a search cut short may have missed a solution
+        if self.gave_up.is_none() {
+            self.mark_dead(&board);
+        }
         None
     }
 }
 
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver::new()
+    }
+}
+
+/// Solver for a board read with `Board::from_str`.
+impl FromStr for Solver {
+    type Err = ParseError;
+
+    fn from_str(b_str: &str) -> Result<Solver, ParseError> {
+        Ok(Solver::from_board(b_str.parse()?))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,7 +346,8 @@ mod tests {
   ---  
   ---  
 ",
-        );
+        )
+        .expect("valid board");
 
         let expected = vec![Move {
             from: Position { x: 4, y: 3 },
@@ -93,7 +371,8 @@ mod tests {
   ---  
   ---  
 ",
-        );
+        )
+        .expect("valid board");
 
         let expected = vec![
             Move {
@@ -122,7 +401,8 @@ mod tests {
   ---  
   ---  
 ",
-        );
+        )
+        .expect("valid board");
 
         let expected = vec![
             Move {
@@ -145,7 +425,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "solves all but does not have the expected steps"]
     fn solve_all() {
         let mut solver = Solver::from_str(
             "
@@ -156,25 +435,185 @@ mod tests {
 *******
   ***  
   ***  
+",
+        )
+        .expect("valid board");
+
+        let got = solver.solve().expect("There is a valida solution here");
+        let replay = solver.verify(&got).expect("all moves are valid");
+
+        assert_eq!(31, got.len());
+        assert!(replay.goal_reached);
+    }
+
+    #[test]
+    fn cache_is_used_and_cleared() {
+        let mut solver = Solver::new();
+        solver.solve().expect("full board has a solution");
+        assert!(solver.cache_hits() > 0);
+
+        solver.clear_cache();
+        assert_eq!(0, solver.cache_hits());
+        assert!(solver.dead_positions.is_empty());
+    }
+
+    #[test]
+    fn pagodas_prune_boards_below_target() {
+        // a board with a single row
+        let board = Board::deserialize(
+            "
+       
+       
+       
+*-*-*-*
+       
+       
+       
 ",
         );
+        // a single piece has value at least -1, the board has value -2.
+        let mut weights = [[0; 7]; 7];
+        weights[3] = [-1, 1, 0, 1, 0, 1, -1];
+        let mut solver = Solver::from_board(board)
+            .with_pagodas(vec![Pagoda::from_array(weights)])
+            .expect("valid pagoda for a single row");
 
-        let expected = vec![
-            Move {
-                from: Position { x: 4, y: 4 },
-                to: Position { x: 4, y: 2 },
-            },
-            Move {
-                from: Position { x: 3, y: 2 },
-                to: Position { x: 5, y: 2 },
-            },
-        ];
+        assert_eq!(None, solver.solve());
+        assert_eq!(1, solver.pruned);
+    }
 
-        let got = solver.solve().expect("There is a valida solution here");
+    #[test]
+    fn invalid_pagodas_are_rejected() {
+        let mut weights = [[0; 7]; 7];
+        weights[3][3] = 1;
+        assert!(Solver::new()
+            .with_pagodas(vec![Pagoda::from_array(weights)])
+            .is_err());
+    }
 
-        println!("number of moves {}", got.len());
-        println!("number moves {}", solver.count);
+    #[test]
+    fn solve_with_english_pagodas() {
+        let mut solver = Solver::new()
+            .with_pagodas(Pagoda::english())
+            .expect("valid pagodas");
+        assert!(solver.solve().is_some());
+    }
 
-        assert_eq!(expected, got);
+    #[test]
+    fn impossible_class_is_not_searched() {
+        let mut solver = Solver::from_str(
+            "
+  ---  
+  ---  
+-------
+--***--
+-------
+  ---  
+  ---  
+",
+        )
+        .expect("valid board");
+        assert_eq!(Outcome::Impossible(Proof::PositionClass), solver.search());
+        assert_eq!(0, solver.count);
+    }
+
+    #[test]
+    fn impossible_after_search() {
+        let mut solver = Solver::from_str(
+            "
+  ---  
+  ---  
+-------
+--*-*--
+-------
+  ---  
+  ---  
+",
+        )
+        .expect("valid board");
+        assert_eq!(Outcome::Impossible(Proof::Search), solver.search());
+    }
+
+    #[test]
+    fn central_game() {
+        let board = Board::new();
+        let center = Position { x: 3, y: 3 };
+        let mut solver = Solver::new()
+            .with_goal(Goal::piece_at(&board, center))
+            .with_pagodas(Pagoda::english())
+            .expect("valid pagodas");
+
+        let solution = solver.solve().expect("the central game has a solution");
+        let replay = solver.verify(&solution).expect("all moves are valid");
+        assert!(replay.goal_reached);
+        assert_eq!(vec![center], replay.last().find_pieces());
+    }
+
+    #[test]
+    fn impossible_final_hole() {
+        let board = Board::new();
+        let mut solver = Solver::new().with_goal(Goal::piece_at(&board, Position { x: 2, y: 2 }));
+        assert_eq!(Outcome::Impossible(Proof::PositionClass), solver.search());
+    }
+
+    #[test]
+    fn solve_to_pattern() {
+        let mut solver = Solver::new().with_goal(Goal::Predicate(Box::new(|b: &Board| {
+            b.count_pieces() == 2 && b.find_moves().is_empty()
+        })));
+        let solution = solver
+            .solve()
+            .expect("there are blocked boards with 2 pieces");
+        assert_eq!(30, solution.len());
+    }
+
+    #[test]
+    fn french_board_from_center_is_impossible() {
+        let mut solver = Solver::from_board(Board::french());
+        assert_eq!(Outcome::Impossible(Proof::PositionClass), solver.search());
+    }
+
+    #[test]
+    fn solve_on_other_geometries() {
+        for board in [
+            Board::french(),
+            Board::wiegleb(),
+            Board::diamond(),
+            Board::asymmetric(),
+        ] {
+            let center = Position {
+                x: board.size() / 2,
+                y: board.size() / 2,
+            };
+            let mut solver = Solver::from_board(board.clone()).with_goal(Goal::Predicate(
+                Box::new(move |b: &Board| {
+                    b.count_pieces() == 28 && b.at(center) == crate::board::Space::Occupied
+                }),
+            ));
+            let solution = solver.solve().expect("there is a solution");
+            assert_eq!(board.count_pieces() as usize - 28, solution.len());
+            assert!(solver.verify(&solution).expect("valid moves").goal_reached);
+        }
+    }
+
+    #[test]
+    fn solve_triangle_from_any_hole() {
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        for hole in full.find_pieces() {
+            let board = full.with_pieces(full.pieces() & !crate::board::bit(hole));
+            let mut solver = Solver::from_board(board);
+            let solution = solver.solve().expect("every start has a solution");
+            let replay = solver.verify(&solution).expect("valid moves");
+            assert_eq!(1, replay.last().count_pieces());
+        }
+    }
+
+    #[test]
+    fn from_str_reports_invalid_boards() {
+        let err = Solver::from_str("***\n*x*\n***")
+            .err()
+            .expect("invalid board");
+        assert_eq!((2, 2), (err.line, err.column));
+        assert_eq!(Board::default(), *Solver::default().board());
     }
 }
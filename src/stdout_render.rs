@@ -7,12 +7,20 @@
 //! a GUI representation of it.
 
 use crate::board::Board;
+use std::fmt;
 
 /// this is a comment about the print function
 pub fn print_board(b: &Board) {
     println!("{}", b.to_str())
 }
 
+/// Same as `serialize`, one row per line.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_str())
+    }
+}
+
 impl Board {
     fn to_str(&self) -> String {
         self.serialize()
@@ -50,4 +58,10 @@ mod tests {
 ";
         assert_eq!(expected, full_board.to_str());
     }
+
+    #[test]
+    fn display_is_the_serialized_board() {
+        let board = Board::french();
+        assert_eq!(board.serialize(), board.to_string());
+    }
 }
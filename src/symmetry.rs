@@ -0,0 +1,446 @@
+//! Board symmetries
+//!
+//! The square board can be rotated and mirrored in 8 different ways, and
+//! the triangular board in 6.
+//! Positions that are a rotation or a mirror of each other play the same,
+//! so each group of them can be represented by a single canonical board.
+//! The `Transform` that takes a board to its canonical form can also map
+//! moves and positions, so a solution found for the canonical board can
+//! be mapped back to the original one with the inverse transform.
+//!
+//! The triangle is stored skewed, row `y` holding the holes `0..=y`, so its
+//! rotations and two of its mirrors are not symmetries of the square. They
+//! are only looked for on boards with triangular jumps, whose symmetries
+//! are then some of the 6 of the triangle, even for a shape like the
+//! hexagon that has more.
+
+use crate::board::{bit, positions, Bits, Board};
+use crate::move_struct::Move;
+use crate::position::Position;
+use crate::rules::Rules;
+
+/// One of the 8 symmetries of the square, or of the 6 symmetries of the
+/// skewed triangle. Rotations are clockwise as the board is printed, with
+/// y growing downwards.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// mirror left and right
+    FlipHorizontal,
+    /// mirror top and bottom
+    FlipVertical,
+    /// mirror over the diagonal from the top left corner
+    FlipDiagonal,
+    /// mirror over the diagonal from the top right corner, on the triangle
+    /// the mirror that keeps the bottom left corner
+    FlipAntiDiagonal,
+    /// triangle rotated a third of a turn
+    Rotate120,
+    /// triangle rotated two thirds of a turn
+    Rotate240,
+    /// triangle mirror that keeps the top corner
+    FlipThroughTop,
+    /// triangle mirror that keeps the bottom right corner
+    FlipThroughBottomRight,
+}
+
+use Transform::*;
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Identity,
+        Rotate90,
+        Rotate180,
+        Rotate270,
+        FlipHorizontal,
+        FlipVertical,
+        FlipDiagonal,
+        FlipAntiDiagonal,
+    ];
+
+    /// The symmetries of the skewed triangle.
+    pub const TRIANGLE: [Transform; 6] = [
+        Identity,
+        Rotate120,
+        Rotate240,
+        FlipAntiDiagonal,
+        FlipThroughTop,
+        FlipThroughBottomRight,
+    ];
+
+    /// The transform that undoes this one.
+    pub fn inverse(&self) -> Transform {
+        match self {
+            Rotate90 => Rotate270,
+            Rotate270 => Rotate90,
+            Rotate120 => Rotate240,
+            Rotate240 => Rotate120,
+            t => *t,
+        }
+    }
+
+    /// Maps a position of a board with `size` rows and columns.
+    ///
+    /// Panics if a transform of the triangle takes the position off the
+    /// board, which does not happen for the holes of a board it is a
+    /// symmetry of.
+    pub fn apply_position(&self, p: Position, size: usize) -> Position {
+        self.try_apply_position(p, size)
+            .unwrap_or_else(|| panic!("{:?} takes {:?} off the board", self, p))
+    }
+
+    /// Maps a position, or `None` if it lands off the board.
+    fn try_apply_position(&self, p: Position, size: usize) -> Option<Position> {
+        let last = size as isize - 1;
+        let (x, y) = (p.x as isize, p.y as isize);
+        let (x, y) = match self {
+            Identity => (x, y),
+            Rotate90 => (last - y, x),
+            Rotate180 => (last - x, last - y),
+            Rotate270 => (y, last - x),
+            FlipHorizontal => (last - x, y),
+            FlipVertical => (x, last - y),
+            FlipDiagonal => (y, x),
+            FlipAntiDiagonal => (last - y, last - x),
+            Rotate120 => (last - y, last - y + x),
+            Rotate240 => (y - x, last - x),
+            FlipThroughTop => (y - x, y),
+            FlipThroughBottomRight => (x, last - y + x),
+        };
+        let on_board = |v: isize| (0..size as isize).contains(&v);
+        (on_board(x) && on_board(y)).then_some(Position {
+            x: x as usize,
+            y: y as usize,
+        })
+    }
+
+    /// Maps the difference between two positions, which does not depend
+    /// on the size of the board.
+    pub fn apply_step(&self, (dx, dy): (i32, i32)) -> (i32, i32) {
+        match self {
+            Identity => (dx, dy),
+            Rotate90 => (-dy, dx),
+            Rotate180 => (-dx, -dy),
+            Rotate270 => (dy, -dx),
+            FlipHorizontal => (-dx, dy),
+            FlipVertical => (dx, -dy),
+            FlipDiagonal => (dy, dx),
+            FlipAntiDiagonal => (-dy, -dx),
+            Rotate120 => (-dy, dx - dy),
+            Rotate240 => (dy - dx, -dx),
+            FlipThroughTop => (dy - dx, dy),
+            FlipThroughBottomRight => (dx, dx - dy),
+        }
+    }
+
+    pub fn apply_move(&self, m: Move, size: usize) -> Move {
+        Move {
+            from: self.apply_position(m.from, size),
+            to: self.apply_position(m.to, size),
+        }
+    }
+}
+
+impl Board {
+    /// The board rotated or mirrored by `t`.
+    pub fn transform(&self, t: Transform) -> Board {
+        let map = |bits| {
+            positions(bits)
+                .map(|p| bit(t.apply_position(p, self.size())))
+                .fold(0, |all, b| all | b)
+        };
+        Board::from_bits(
+            map(self.pieces()),
+            map(self.holes()),
+            self.size(),
+            self.rules(),
+        )
+    }
+
+    /// Transforms that map the shape of the board and the directions of
+    /// its jumps onto themselves, ignoring the pieces. Always contains
+    /// `Identity`. Taken from the symmetries of the triangle for boards
+    /// with triangular jumps, and of the square otherwise.
+    pub fn symmetries(&self) -> Vec<Transform> {
+        let directions = self.rules().directions();
+        let keeps_directions = |t: &Transform| {
+            directions
+                .iter()
+                .all(|d| directions.contains(&t.apply_step(*d)))
+        };
+        let keeps_holes = |t: &Transform| {
+            positions(self.holes()).all(|p| {
+                t.try_apply_position(p, self.size())
+                    .is_some_and(|image| self.holes() & bit(image) != 0)
+            })
+        };
+        let candidates = match self.rules() {
+            Rules::Triangular => Transform::TRIANGLE.to_vec(),
+            _ => Transform::ALL.to_vec(),
+        };
+        candidates
+            .into_iter()
+            .filter(|t| keeps_holes(t) && keeps_directions(t))
+            .collect()
+    }
+
+    /// Canonical form of the board and the transform that takes this
+    /// board to it. All boards that are rotations or mirrors of each other
+    /// have the same canonical form.
+    pub fn canonical(&self) -> (Board, Transform) {
+        self.symmetries()
+            .into_iter()
+            .map(|t| (self.transform(t), t))
+            .min_by_key(|(b, _)| b.hash_code())
+            .expect("identity is always a symmetry")
+    }
+}
+
+/// Canonical forms of the pieces of boards with one shape. Each symmetry
+/// has a table with the image of every byte of the pieces, so mapping a
+/// board takes a lookup per byte instead of moving its pieces one by one.
+/// Meant for sweeps over millions of boards.
+pub(crate) struct Canonicalizer {
+    /// for each symmetry, the image of each value of each byte
+    tables: Vec<Vec<[Bits; 256]>>,
+}
+
+impl Canonicalizer {
+    /// Canonicalizer for the shape of `board` using the `symmetries`,
+    /// which must map the shape onto itself and contain `Identity`.
+    pub(crate) fn new(board: &Board, symmetries: &[Transform]) -> Canonicalizer {
+        let bytes = (Bits::BITS as usize).div_ceil(8);
+        let tables = symmetries
+            .iter()
+            .map(|t| {
+                (0..bytes)
+                    .map(|byte| {
+                        let mut table = [0; 256];
+                        for (value, image) in table.iter_mut().enumerate() {
+                            let bits = ((value as Bits) << (8 * byte)) & board.holes();
+                            *image = positions(bits)
+                                .map(|p| bit(t.apply_position(p, board.size())))
+                                .fold(0, |all, b| all | b);
+                        }
+                        table
+                    })
+                    .collect()
+            })
+            .collect();
+        Canonicalizer { tables }
+    }
+
+    /// Canonicalizer using the symmetries that leave `board` unchanged,
+    /// so the boards reachable from it are mapped to boards reachable from
+    /// it too.
+    pub(crate) fn fixing(board: &Board) -> Canonicalizer {
+        let symmetries: Vec<Transform> = board
+            .symmetries()
+            .into_iter()
+            .filter(|t| board.transform(*t) == *board)
+            .collect();
+        Canonicalizer::new(board, &symmetries)
+    }
+
+    fn apply(table: &[[Bits; 256]], pieces: Bits) -> Bits {
+        table
+            .iter()
+            .enumerate()
+            .map(|(byte, images)| images[(pieces >> (8 * byte)) as usize & 0xff])
+            .fold(0, |all, b| all | b)
+    }
+
+    /// Same as the pieces of `Board::canonical` when using all the
+    /// symmetries of the board.
+    pub(crate) fn canonical(&self, pieces: Bits) -> Bits {
+        self.tables
+            .iter()
+            .map(|table| Canonicalizer::apply(table, pieces))
+            .min()
+            .expect("identity is always a symmetry")
+    }
+
+    /// Number of different boards the symmetries take the pieces to.
+    pub(crate) fn orbit_size(&self, pieces: Bits) -> u64 {
+        let mut images: Vec<Bits> = self
+            .tables
+            .iter()
+            .map(|table| Canonicalizer::apply(table, pieces))
+            .collect();
+        images.sort_unstable();
+        images.dedup();
+        images.len() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    fn asymmetric_board() -> Board {
+        Board::deserialize(
+            "
+  *-*  
+  **-  
+-*-*---
+-------
+---**--
+  -*-  
+  ---  
+",
+        )
+    }
+
+    #[test]
+    fn full_board_has_all_symmetries() {
+        assert_eq!(Transform::ALL.to_vec(), Board::new().symmetries());
+    }
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let board = asymmetric_board();
+        for t in Transform::ALL {
+            assert_eq!(board, board.transform(t).transform(t.inverse()));
+        }
+    }
+
+    #[test]
+    fn rotate_90_is_clockwise() {
+        let p = Position { x: 3, y: 0 };
+        assert_eq!(Position { x: 6, y: 3 }, Rotate90.apply_position(p, 7));
+    }
+
+    #[test]
+    fn transformed_boards_have_same_canonical_form() {
+        let board = asymmetric_board();
+        let (canonical, t) = board.canonical();
+        assert_eq!(canonical, board.transform(t));
+        for other in Transform::ALL {
+            assert_eq!(canonical, board.transform(other).canonical().0);
+        }
+    }
+
+    #[test]
+    fn transformed_moves_stay_valid() {
+        let board = asymmetric_board();
+        let (canonical, t) = board.canonical();
+        for m in board.find_moves() {
+            let expected = board
+                .move_piece(m.from, m.to)
+                .expect("valid move")
+                .transform(t);
+            let mapped = t.apply_move(m, board.size());
+            assert_eq!(
+                expected,
+                canonical
+                    .move_piece(mapped.from, mapped.to)
+                    .expect("valid move")
+            );
+        }
+    }
+
+    #[test]
+    fn canonical_solution_maps_back() {
+        let board = Board::deserialize(
+            "
+  ---  
+  ---  
+-*-*---
+----*--
+----*--
+  ---  
+  ---  
+",
+        );
+        let (canonical, t) = board.canonical();
+        let solution = Solver::from_board(canonical)
+            .solve()
+            .expect("has a solution");
+
+        let mut replay = board;
+        for m in solution {
+            let m = t.inverse().apply_move(m, replay.size());
+            replay = replay.move_piece(m.from, m.to).expect("valid move");
+        }
+        assert_eq!(1, replay.find_pieces().len());
+    }
+
+    #[test]
+    fn triangle_symmetries_keep_jump_directions() {
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        assert_eq!(Transform::TRIANGLE.to_vec(), full.symmetries());
+        assert_eq!(Transform::TRIANGLE.to_vec(), Board::triangle().symmetries());
+        for t in Transform::TRIANGLE {
+            assert_eq!(full, full.transform(t));
+            assert_eq!(full, full.transform(t).transform(t.inverse()));
+        }
+
+        let top = Position { x: 0, y: 0 };
+        let bottom_left = Position { x: 0, y: 4 };
+        let bottom_right = Position { x: 4, y: 4 };
+        assert_eq!(bottom_right, Rotate120.apply_position(top, 5));
+        assert_eq!(bottom_left, Rotate120.apply_position(bottom_right, 5));
+        assert_eq!(bottom_left, Rotate240.apply_position(top, 5));
+        assert_eq!(top, FlipThroughTop.apply_position(top, 5));
+        assert_eq!(bottom_left, FlipAntiDiagonal.apply_position(bottom_left, 5));
+        assert_eq!(
+            bottom_right,
+            FlipThroughBottomRight.apply_position(bottom_right, 5)
+        );
+
+        // each corner has a mirror keeping it, the other holes of an edge
+        // are kept by the mirror of the opposite corner
+        let without = |x, y| full.with_pieces(full.pieces() & !bit(Position { x, y }));
+        for (x, y) in [(0, 0), (0, 4), (4, 4), (0, 2), (1, 2)] {
+            let board = without(x, y);
+            let fixing: Vec<Transform> = board
+                .symmetries()
+                .into_iter()
+                .filter(|t| board.transform(*t) == board)
+                .collect();
+            assert_eq!(2, fixing.len(), "{}", board);
+        }
+    }
+
+    #[test]
+    fn triangle_canonical_forms() {
+        let full = Board::triangle().with_pieces(Board::triangle().holes());
+        let without = |x, y| full.with_pieces(full.pieces() & !bit(Position { x, y }));
+        let corners = [(0, 0), (0, 4), (4, 4)].map(|(x, y)| without(x, y).canonical().0);
+        assert_eq!(corners[0], corners[1]);
+        assert_eq!(corners[0], corners[2]);
+        let canonicalizer = Canonicalizer::new(&full, &full.symmetries());
+        for board in [without(0, 0), without(2, 3), without(1, 2)] {
+            let (canonical, t) = board.canonical();
+            assert_eq!(canonical, board.transform(t));
+            assert_eq!(canonical.pieces(), canonicalizer.canonical(board.pieces()));
+        }
+    }
+
+    #[test]
+    fn canonicalizer_matches_canonical() {
+        for board in [Board::new(), Board::french(), Board::triangle()] {
+            let canonicalizer = Canonicalizer::new(&board, &board.symmetries());
+            let mut boards = vec![board.clone()];
+            for _ in 0..4 {
+                let next = boards.last().expect("not empty").clone();
+                let m = *next.find_moves().last().expect("has moves");
+                boards.push(next.move_piece(m.from, m.to).expect("valid move"));
+            }
+            for b in boards {
+                let (canonical, _) = b.canonical();
+                assert_eq!(canonical.pieces(), canonicalizer.canonical(b.pieces()));
+                let orbit: std::collections::HashSet<Bits> = b
+                    .symmetries()
+                    .into_iter()
+                    .map(|t| b.transform(t).pieces())
+                    .collect();
+                assert_eq!(orbit.len() as u64, canonicalizer.orbit_size(b.pieces()));
+            }
+        }
+    }
+}
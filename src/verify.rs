@@ -0,0 +1,97 @@
+//! Solution verifier
+//!
+//! Replays a list of moves one by one, keeping the board after each of
+//! them, so a solution can be checked and shown step by step. The first
+//! move that is not valid stops the replay and is reported with its index.
+
+use crate::board::Board;
+use crate::error::Error;
+use crate::move_struct::Move;
+use crate::solver::Solver;
+
+/// Boards of a replayed list of moves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    /// the starting board followed by the board after each move
+    pub boards: Vec<Board>,
+    /// the last board reaches the goal of the solver
+    pub goal_reached: bool,
+}
+
+impl Replay {
+    /// Board after the last move.
+    pub fn last(&self) -> &Board {
+        self.boards.last().expect("replay has the starting board")
+    }
+}
+
+impl Board {
+    /// This board followed by the board after each move, or the index of
+    /// the first move that is not valid.
+    pub fn replay(&self, moves: &[Move]) -> Result<Vec<Board>, Error<'static>> {
+        let mut boards = Vec::with_capacity(moves.len() + 1);
+        boards.push(self.clone());
+        for (i, m) in moves.iter().enumerate() {
+            let board = boards[i]
+                .move_piece(m.from, m.to)
+                .map_err(|err| Error::InvalidMoveAt(i, Box::new(err)))?;
+            boards.push(board);
+        }
+        Ok(boards)
+    }
+}
+
+impl Solver {
+    /// Replays the moves on the board of the solver and checks if they
+    /// reach its goal.
+    pub fn verify(&self, moves: &[Move]) -> Result<Replay, Error<'static>> {
+        let boards = self.board().replay(moves)?;
+        let goal_reached = self
+            .goal()
+            .reached(boards.last().expect("replay has the start"));
+        Ok(Replay {
+            boards,
+            goal_reached,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Position;
+
+    #[test]
+    fn replay_step_by_step() {
+        let moves = vec![
+            Move {
+                from: Position { x: 3, y: 1 },
+                to: Position { x: 3, y: 3 },
+            },
+            Move {
+                from: Position { x: 1, y: 2 },
+                to: Position { x: 3, y: 2 },
+            },
+        ];
+        let replay = Solver::new().verify(&moves).expect("valid moves");
+        assert_eq!(3, replay.boards.len());
+        assert_eq!(Board::new(), replay.boards[0]);
+        assert_eq!(31, replay.boards[1].count_pieces());
+        assert_eq!(30, replay.last().count_pieces());
+        assert!(!replay.goal_reached);
+    }
+
+    #[test]
+    fn first_invalid_move_is_reported() {
+        let m = Move {
+            from: Position { x: 3, y: 1 },
+            to: Position { x: 3, y: 3 },
+        };
+        match Board::new().replay(&[m, m, m]) {
+            Err(Error::InvalidMoveAt(1, err)) => {
+                assert!(matches!(*err, Error::SpacesInvolvedNotCorrect(..)))
+            }
+            other => panic!("expected the second move to fail, got {:?}", other),
+        }
+    }
+}